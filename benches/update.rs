@@ -0,0 +1,79 @@
+// Benchmarks for stepping the world, run with 'cargo bench'. Each benchmark times a single step from the same starting
+// state every time. To compare the serial and parallel updates, save a baseline from one and compare the other against
+// it, e.g.:
+//     cargo bench -- --save-baseline serial
+//     cargo bench --features parallel -- --baseline serial
+// To compare two checkouts, build each in its own target directory and share the baselines (which are otherwise kept in
+// the target directory) with CRITERION_HOME, e.g.:
+//     CARGO_TARGET_DIR=/tmp/before CRITERION_HOME=/tmp/criterion cargo bench -- --save-baseline before
+//     CARGO_TARGET_DIR=/tmp/after CRITERION_HOME=/tmp/criterion cargo bench -- --baseline before
+use criterion::measurement::WallTime;
+use criterion::{
+    criterion_group, criterion_main, BatchSize, BenchmarkGroup, BenchmarkId, Criterion,
+};
+use rust_fall::engine::{CellType, World};
+use rust_fall::examples;
+
+const SIZES: [usize; 3] = [128, 512, 1024]; // World sizes (in number of cells)
+const LARGE_SCALES: [usize; 2] = [16, 32]; // Scales of the first example for the large grids (2048 and 4096 cells)
+
+// Times a step of the world created by the setup function
+fn bench_step<F>(group: &mut BenchmarkGroup<WallTime>, name: &str, size: usize, setup: F)
+where
+    F: Fn() -> World,
+{
+    group.bench_function(BenchmarkId::new(name, size), |b| {
+        b.iter_batched(
+            &setup,
+            |mut world| {
+                world.update();
+                world
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn filled(size: usize, cell_type: CellType, rows: usize) -> World {
+    let mut world = World::new(size);
+    for row in world.grid.rows - rows..world.grid.rows {
+        for column in 0..world.grid.columns {
+            world.grid.cells[row][column].cell_type = cell_type;
+        }
+    }
+    world
+}
+
+fn update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    for size in SIZES {
+        bench_step(&mut group, "empty", size, || World::new(size));
+        // The first example scaled up to the size of the world
+        bench_step(&mut group, "example_1", size, || {
+            examples::populate_world(&examples::EXAMPLE_1, size / examples::EXAMPLE_1.0)
+        });
+        bench_step(&mut group, "water", size, || {
+            filled(size, CellType::Water, size)
+        });
+        // Sand filling the top half of the world, falling into the empty bottom half
+        bench_step(&mut group, "falling_sand", size, || {
+            filled(size, CellType::Sand, size / 2)
+        });
+    }
+    group.finish();
+}
+
+fn update_large(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_large");
+    group.sample_size(10);
+    for scale in LARGE_SCALES {
+        let size = examples::EXAMPLE_1.0 * scale;
+        bench_step(&mut group, "example_1", size, || {
+            examples::populate_world(&examples::EXAMPLE_1, scale)
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, update, update_large);
+criterion_main!(benches);
@@ -0,0 +1,114 @@
+/*
+ * C interface to the rust-fall falling sand engine.
+ *
+ * Build the library with `cargo build --release --lib`, which produces
+ * target/release/librust_fall.so (librust_fall.dylib on macOS, rust_fall.dll on Windows), and link against it.
+ *
+ * Worlds are opaque, and are created with rust_fall_world_new or rust_fall_world_load and freed with
+ * rust_fall_world_free. Unless stated otherwise, every function that takes a world must be given one that has been
+ * created and not yet freed, and a world must not be used from more than one thread at a time.
+ *
+ * Positions are given as a row and a column, with row 0 at the bottom of the world (gravity pulls towards row 0 by
+ * default) and column 0 on the left.
+ */
+
+#ifndef RUST_FALL_H
+#define RUST_FALL_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Version of this interface, returned by rust_fall_version */
+#define RUST_FALL_VERSION 1
+
+/* Cell types, as passed to and returned by the functions below */
+enum RustFallCellType {
+    RUST_FALL_EMPTY = 0,
+    RUST_FALL_WALL = 1,
+    RUST_FALL_WOOD = 2,
+    RUST_FALL_SAND = 3,
+    RUST_FALL_WATER = 4,
+    RUST_FALL_OIL = 5,
+    RUST_FALL_STEAM = 6,
+    RUST_FALL_LAVA = 7,
+    RUST_FALL_STONE = 8,
+    RUST_FALL_OBSIDIAN = 9,
+    RUST_FALL_FIRE = 10,
+    RUST_FALL_ACID = 11,
+    RUST_FALL_GLASS = 12,
+    RUST_FALL_ICE = 13,
+    RUST_FALL_SNOW = 14,
+    RUST_FALL_SEED = 15,
+    RUST_FALL_PLANT = 16,
+    RUST_FALL_GUNPOWDER = 17,
+    RUST_FALL_TNT = 18,
+    RUST_FALL_SMOKE = 19,
+    RUST_FALL_TOXIC_GAS = 20,
+    RUST_FALL_CUSTOM_1 = 21,
+    RUST_FALL_CUSTOM_2 = 22,
+    RUST_FALL_CUSTOM_3 = 23,
+    RUST_FALL_CUSTOM_4 = 24,
+};
+
+typedef struct RustFallWorld RustFallWorld;
+
+/* Returns the version of the interface implemented by the library, to be compared with RUST_FALL_VERSION */
+uint32_t rust_fall_version(void);
+
+/* Creates an empty world, returning NULL if either dimension is zero. The world is seeded randomly. */
+RustFallWorld *rust_fall_world_new(size_t rows, size_t columns);
+
+/* Frees a world. Does nothing if the world is NULL. */
+void rust_fall_world_free(RustFallWorld *world);
+
+size_t rust_fall_world_rows(const RustFallWorld *world);
+size_t rust_fall_world_columns(const RustFallWorld *world);
+
+/* Reseeds the world's random number generator, so that the simulation runs the same way every time from this point */
+void rust_fall_world_seed(RustFallWorld *world, uint64_t seed);
+
+/* Performs the given number of steps of the simulation */
+void rust_fall_world_step(RustFallWorld *world, size_t steps);
+
+/* Returns the type of the cell (a RustFallCellType), or -1 if the position is outside the world */
+int32_t rust_fall_world_get_cell(const RustFallWorld *world, size_t row, size_t column);
+
+/*
+ * Sets the type of the cell (a RustFallCellType). Returns false, leaving the world unchanged, if the position is
+ * outside the world or the type is unknown.
+ */
+bool rust_fall_world_set_cell(RustFallWorld *world, size_t row, size_t column, uint8_t cell_type);
+
+/*
+ * Copies the type of every cell (a RustFallCellType) into the buffer, row by row starting with the bottom row, so that
+ * the cell at (row, column) is at buffer[row * columns + column]. Returns the number of cells in the world (rows *
+ * columns). Nothing is copied if the buffer is NULL or length is less than that, so the size of the buffer needed can
+ * be found by passing a length of 0.
+ */
+size_t rust_fall_world_read_cells(const RustFallWorld *world, uint8_t *buffer, size_t length);
+
+/*
+ * Saves the types of the world's cells as text, one line per row starting with the top row and one character per
+ * cell, into the buffer, followed by a null character. Returns the length of the text, not counting the null
+ * character. As with snprintf, nothing is written if the buffer is NULL or length is not greater than that, so the
+ * size of the buffer needed can be found by passing a length of 0.
+ */
+size_t rust_fall_world_save(const RustFallWorld *world, char *buffer, size_t length);
+
+/*
+ * Creates a world from text saved by rust_fall_world_save (a null terminated string), returning NULL if the text is
+ * NULL or not a valid world. Only the types of the cells are saved, so the loaded world is seeded randomly, its cells
+ * are at rest and gravity points down.
+ */
+RustFallWorld *rust_fall_world_load(const char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUST_FALL_H */
@@ -0,0 +1,95 @@
+/*
+ * Exercises the C interface (see rust_fall.h). Build and run it from the root of the repository with:
+ *     cargo build --lib
+ *     cc -Wall -Wextra -o target/ffi-test ffi/test.c -Iffi -Ltarget/debug -lrust_fall
+ *     LD_LIBRARY_PATH=target/debug target/ffi-test
+ * It prints each check and exits with a non-zero status if any fail.
+ */
+
+#include "rust_fall.h"
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static int failures = 0;
+
+static void check(bool passed, const char *description) {
+    printf("%s: %s\n", passed ? "ok" : "FAILED", description);
+    if (!passed) {
+        failures++;
+    }
+}
+
+static size_t count(const RustFallWorld *world, enum RustFallCellType cell_type) {
+    size_t cells = rust_fall_world_read_cells(world, NULL, 0);
+    uint8_t *buffer = malloc(cells);
+    rust_fall_world_read_cells(world, buffer, cells);
+    size_t total = 0;
+    for (size_t i = 0; i < cells; i++) {
+        if (buffer[i] == cell_type) {
+            total++;
+        }
+    }
+    free(buffer);
+    return total;
+}
+
+int main(void) {
+    check(rust_fall_version() == RUST_FALL_VERSION, "library version matches header");
+    check(rust_fall_world_new(0, 10) == NULL, "world with no rows is rejected");
+
+    RustFallWorld *world = rust_fall_world_new(16, 24);
+    check(world != NULL, "world is created");
+    check(rust_fall_world_rows(world) == 16 && rust_fall_world_columns(world) == 24, "world has the requested size");
+    check(rust_fall_world_get_cell(world, 3, 5) == RUST_FALL_EMPTY, "new world is empty");
+
+    /* Cell access */
+    check(rust_fall_world_set_cell(world, 15, 5, RUST_FALL_SAND), "cell is set");
+    check(rust_fall_world_get_cell(world, 15, 5) == RUST_FALL_SAND, "cell reads back");
+    check(!rust_fall_world_set_cell(world, 16, 5, RUST_FALL_SAND), "cell outside the world is rejected");
+    check(!rust_fall_world_set_cell(world, 0, 0, 200), "unknown cell type is rejected");
+    check(rust_fall_world_get_cell(world, 0, 24) == -1, "cell outside the world reads as -1");
+
+    /* Bulk readout */
+    check(rust_fall_world_read_cells(world, NULL, 0) == 16 * 24, "readout size is reported");
+    uint8_t cells[16 * 24];
+    rust_fall_world_read_cells(world, cells, sizeof cells);
+    check(cells[15 * 24 + 5] == RUST_FALL_SAND, "readout is row by row from the bottom");
+
+    /* Stepping: the sand falls to the bottom row */
+    rust_fall_world_seed(world, 42);
+    rust_fall_world_step(world, 50);
+    check(rust_fall_world_get_cell(world, 0, 5) == RUST_FALL_SAND, "sand falls to the bottom");
+    check(count(world, RUST_FALL_SAND) == 1, "sand is conserved");
+
+    /* Saving and loading */
+    for (size_t column = 0; column < 24; column++) {
+        rust_fall_world_set_cell(world, 8, column, RUST_FALL_WALL);
+    }
+    size_t length = rust_fall_world_save(world, NULL, 0);
+    check(length == 16 * 25, "saved length is reported");
+    char *text = malloc(length + 1);
+    check(rust_fall_world_save(world, text, length + 1) == length && strlen(text) == length, "world is saved");
+    RustFallWorld *loaded = rust_fall_world_load(text);
+    check(loaded != NULL, "saved world loads");
+    check(rust_fall_world_rows(loaded) == 16 && rust_fall_world_columns(loaded) == 24, "loaded world has the same size");
+    uint8_t loaded_cells[16 * 24];
+    rust_fall_world_read_cells(world, cells, sizeof cells);
+    rust_fall_world_read_cells(loaded, loaded_cells, sizeof loaded_cells);
+    check(memcmp(cells, loaded_cells, sizeof cells) == 0, "loaded world has the same cells");
+    check(rust_fall_world_load("..\n.") == NULL, "invalid world is rejected");
+    free(text);
+
+    /* Walls hold water */
+    rust_fall_world_set_cell(loaded, 12, 3, RUST_FALL_WATER);
+    rust_fall_world_step(loaded, 100);
+    check(count(loaded, RUST_FALL_WATER) == 1 && count(loaded, RUST_FALL_WALL) == 24, "water rests on the wall");
+
+    rust_fall_world_free(loaded);
+    rust_fall_world_free(world);
+    rust_fall_world_free(NULL);
+
+    printf("%d failure(s)\n", failures);
+    return failures == 0 ? EXIT_SUCCESS : EXIT_FAILURE;
+}
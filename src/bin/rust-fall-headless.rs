@@ -0,0 +1,9 @@
+// Runs the simulation without a window or terminal interface, controlled by commands read from stdin and answered on
+// stdout (see the protocol module for the commands), e.g.
+//     printf 'fill_rect 10 0 12 63 sand\nstep 100\ncount sand\n' | cargo run --bin rust-fall-headless
+
+use std::io;
+
+fn main() -> io::Result<()> {
+    rust_fall::protocol::run(io::stdin().lock(), io::stdout().lock())
+}
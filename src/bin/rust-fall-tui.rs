@@ -0,0 +1,337 @@
+// A terminal frontend for the simulation, for use where no window can be opened (e.g. over SSH on a headless server).
+//
+// The world is drawn with Unicode upper half block characters, each showing two cells (one above the other) using ANSI
+// truecolour foreground and background colours. Worlds too large for the terminal are scaled down to fit. Only the
+// characters that change between frames are redrawn, to keep the output small over slow connections.
+//
+// Controls:
+//     - Space: pause or resume
+//     - S: perform a single step (while paused)
+//     - R: restart
+//     - C: clear all cells
+//     - M / Shift+M: select the next / previous material
+//     - Left mouse button: paint the selected material (in terminals that report mouse events)
+//     - Arrow keys: change the direction of gravity, G: switch gravity off or back on
+//     - + / -: increase or decrease the strength of gravity
+//     - L: switch depth shading of liquids on or off
+//     - Q or Esc: quit
+
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton,
+    MouseEventKind,
+};
+use crossterm::style::{Color, Print, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use rust_fall::colours;
+#[cfg(feature = "scripting")]
+use rust_fall::engine::Scripts;
+use rust_fall::engine::{CellType, Gravity, Rules, World};
+use rust_fall::examples;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const TIME_STEP: f32 = 0.02; // in seconds
+const FRAME_TIME: f32 = 1.0 / 30.0; // in seconds
+const MAX_STEPS_PER_FRAME: usize = 10; // Steps performed to keep up with the time step before giving up and drawing
+
+type Rgb = (u8, u8, u8);
+
+// How the world is laid out in the terminal
+struct View {
+    scale: usize, // Width and height of the square of cells shown by each half character
+    columns: u16, // Width of the world in the terminal (in characters)
+    rows: u16, // Height of the world in the terminal (in characters), with the status line below it
+    drawn: Vec<(Rgb, Rgb)>, // Colours of each character as last drawn, so that only changes are redrawn
+}
+
+impl View {
+    fn new(world: &World) -> io::Result<Self> {
+        let (width, height) = terminal::size()?;
+        let width = (width as usize).max(1);
+        let height = (height as usize).saturating_sub(1).max(1) * 2;
+        let scale = world
+            .grid
+            .columns
+            .div_ceil(width)
+            .max(world.grid.rows.div_ceil(height))
+            .max(1);
+        Ok(View {
+            scale,
+            columns: world.grid.columns.div_ceil(scale) as u16,
+            rows: world.grid.rows.div_ceil(scale * 2) as u16,
+            drawn: Vec::new(),
+        })
+    }
+}
+
+fn main() -> io::Result<()> {
+    // Load the rules and the scripts for the custom materials (if there are any) before taking over the terminal, so
+    // that any errors can be seen
+    let rules = {
+        let directory = std::path::Path::new(Rules::DIRECTORY);
+        if directory.is_dir() {
+            let rules = Rules::load_directory(directory)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            (!rules.is_empty()).then(|| Arc::new(rules))
+        } else {
+            None
+        }
+    };
+    #[cfg(feature = "scripting")]
+    let scripts = {
+        let directory = std::path::Path::new(Scripts::DIRECTORY);
+        if directory.is_dir() {
+            let scripts = Scripts::load_directory(directory)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            Some(Arc::new(scripts))
+        } else {
+            None
+        }
+    };
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        cursor::Hide
+    )?;
+    let result = run(
+        &mut stdout,
+        &rules,
+        #[cfg(feature = "scripting")]
+        &scripts,
+    );
+    execute!(
+        stdout,
+        SetForegroundColor(Color::Reset),
+        SetBackgroundColor(Color::Reset),
+        cursor::Show,
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn run(
+    stdout: &mut io::Stdout,
+    rules: &Option<Arc<Rules>>,
+    #[cfg(feature = "scripting")] scripts: &Option<Arc<Scripts>>,
+) -> io::Result<()> {
+    let new_world = || {
+        let mut world = examples::populate_world(&examples::EXAMPLE_1, 1);
+        // Momentum is off by default in the engine, but is on in the window, so turn it on here too
+        world.momentum = true;
+        world.rules.clone_from(rules);
+        #[cfg(feature = "scripting")]
+        world.scripts.clone_from(scripts);
+        world
+    };
+    let mut world = new_world();
+    let mut view = View::new(&world)?;
+    let mut paused = false;
+    let mut material_type = CellType::Sand;
+    let mut depth_shading = false;
+    let time_step = Duration::from_secs_f32(TIME_STEP);
+    let frame_time = Duration::from_secs_f32(FRAME_TIME);
+    let mut time_of_last_update = Instant::now();
+    loop {
+        let frame_start = Instant::now();
+        // Handle input
+        while event::poll(Duration::ZERO)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') => {
+                        paused = !paused;
+                        time_of_last_update = Instant::now();
+                    }
+                    KeyCode::Char('s') if paused => world.update(),
+                    KeyCode::Char('r') => {
+                        world = new_world();
+                        view = View::new(&world)?;
+                        queue!(stdout, Clear(ClearType::All))?;
+                    }
+                    KeyCode::Char('c') => world.clear(),
+                    KeyCode::Char('m') => material_type = cycle(material_type, 1),
+                    KeyCode::Char('M') => {
+                        material_type = cycle(material_type, CellType::ALL.len() - 1)
+                    }
+                    KeyCode::Down => world.gravity = Gravity::Down,
+                    KeyCode::Up => world.gravity = Gravity::Up,
+                    KeyCode::Left => world.gravity = Gravity::Left,
+                    KeyCode::Right => world.gravity = Gravity::Right,
+                    KeyCode::Char('l') => depth_shading = !depth_shading,
+                    KeyCode::Char('g') => {
+                        world.gravity = if world.gravity == Gravity::Off {
+                            Gravity::Down
+                        } else {
+                            Gravity::Off
+                        }
+                    }
+                    KeyCode::Char('+') | KeyCode::Char('=') => world.gravity_strength += 0.25,
+                    KeyCode::Char('-') => {
+                        world.gravity_strength = (world.gravity_strength - 0.25).max(0.25)
+                    }
+                    _ => {}
+                },
+                Event::Mouse(mouse) => {
+                    if matches!(
+                        mouse.kind,
+                        MouseEventKind::Down(MouseButton::Left)
+                            | MouseEventKind::Drag(MouseButton::Left)
+                    ) {
+                        paint(&mut world, &view, mouse.column, mouse.row, material_type);
+                    }
+                }
+                Event::Resize(..) => {
+                    view = View::new(&world)?;
+                    queue!(stdout, Clear(ClearType::All))?;
+                }
+                _ => {}
+            }
+        }
+        // Update world, performing as many steps as necessary to keep up with the target time step
+        if !paused {
+            let mut steps = 0;
+            while time_of_last_update.elapsed() >= time_step {
+                world.update();
+                time_of_last_update += time_step;
+                steps += 1;
+                // Give up on keeping up (the simulation is running slower than the time step) to stay responsive
+                if steps >= MAX_STEPS_PER_FRAME {
+                    time_of_last_update = Instant::now();
+                    break;
+                }
+            }
+        }
+        draw(
+            stdout,
+            &world,
+            &mut view,
+            paused,
+            material_type,
+            depth_shading,
+        )?;
+        // Wait for next frame
+        if let Some(remaining) = frame_time.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
+
+// Returns the world position of the cell shown by the given half character (counting half characters from the top), if
+// there is one
+fn position(world: &World, scale: usize, column: u16, half_row: usize) -> Option<(usize, usize)> {
+    let column = column as usize * scale;
+    let from_top = half_row * scale;
+    if column < world.grid.columns && from_top < world.grid.rows {
+        Some((world.grid.rows - 1 - from_top, column))
+    } else {
+        None
+    }
+}
+
+fn cycle(material_type: CellType, offset: usize) -> CellType {
+    let index = material_type as usize;
+    CellType::ALL[(index + offset) % CellType::ALL.len()]
+}
+
+// Paints the cells shown by the character at the given terminal position
+fn paint(world: &mut World, view: &View, column: u16, row: u16, material_type: CellType) {
+    if column >= view.columns || row >= view.rows {
+        return;
+    }
+    for half_row in [row as usize * 2, row as usize * 2 + 1] {
+        if let Some((world_row, world_column)) = position(world, view.scale, column, half_row) {
+            for paint_row in world_row.saturating_sub(view.scale - 1)..=world_row {
+                for paint_column in
+                    world_column..(world_column + view.scale).min(world.grid.columns)
+                {
+                    world.set_cell(paint_row, paint_column, material_type);
+                }
+            }
+        }
+    }
+}
+
+fn draw(
+    stdout: &mut io::Stdout,
+    world: &World,
+    view: &mut View,
+    paused: bool,
+    material_type: CellType,
+    depth_shading: bool,
+) -> io::Result<()> {
+    let background = colours::BACKGROUND.to_rgb8();
+    let colour = |half_row: usize, column: u16| match position(world, view.scale, column, half_row)
+    {
+        Some((row, column)) => colours::cell_colour(world, row, column, depth_shading).to_rgb8(),
+        None => background,
+    };
+    let characters = view.rows as usize * view.columns as usize;
+    let redraw = view.drawn.len() != characters;
+    if redraw {
+        view.drawn = vec![(background, background); characters];
+    }
+    // Draw world, skipping characters that have not changed since the last frame
+    let mut current: Option<(Rgb, Rgb)> = None;
+    let mut next_position: Option<(u16, u16)> = None;
+    for row in 0..view.rows {
+        for column in 0..view.columns {
+            let colours = (
+                colour(row as usize * 2, column),
+                colour(row as usize * 2 + 1, column),
+            );
+            let index = row as usize * view.columns as usize + column as usize;
+            if !redraw && view.drawn[index] == colours {
+                continue;
+            }
+            view.drawn[index] = colours;
+            if next_position != Some((column, row)) {
+                queue!(stdout, cursor::MoveTo(column, row))?;
+            }
+            if current != Some(colours) {
+                let ((top_red, top_green, top_blue), (bottom_red, bottom_green, bottom_blue)) =
+                    colours;
+                queue!(
+                    stdout,
+                    SetForegroundColor(Color::Rgb {
+                        r: top_red,
+                        g: top_green,
+                        b: top_blue
+                    }),
+                    SetBackgroundColor(Color::Rgb {
+                        r: bottom_red,
+                        g: bottom_green,
+                        b: bottom_blue
+                    })
+                )?;
+                current = Some(colours);
+            }
+            queue!(stdout, Print('▀'))?;
+            next_position = Some((column + 1, row));
+        }
+    }
+    // Draw status line
+    queue!(
+        stdout,
+        SetForegroundColor(Color::Reset),
+        SetBackgroundColor(Color::Reset),
+        cursor::MoveTo(0, view.rows),
+        Clear(ClearType::CurrentLine),
+        Print(format!(
+            "{} | {} | steps {} | gravity {:?} x{} | \
+             space pause, s step, r restart, c clear, m material, l depth shading, q quit",
+            material_type.name(),
+            if paused { "paused" } else { "running" },
+            world.steps,
+            world.gravity,
+            world.gravity_strength
+        ))
+    )?;
+    stdout.flush()
+}
@@ -0,0 +1,130 @@
+// This module defines the colours that cells are drawn in, shared by all of the renderers so that the world looks the
+// same whichever one is used.
+
+use crate::engine::{CellType, World};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Colour {
+    pub red: f32, // Each component is between 0 and 1
+    pub green: f32,
+    pub blue: f32,
+}
+
+impl Colour {
+    const fn new(red: f32, green: f32, blue: f32) -> Self {
+        Colour { red, green, blue }
+    }
+
+    // Returns the colour with each component multiplied by the factor (and kept between 0 and 1)
+    fn scaled(self, factor: f32) -> Self {
+        Colour::new(
+            (self.red * factor).clamp(0.0, 1.0),
+            (self.green * factor).clamp(0.0, 1.0),
+            (self.blue * factor).clamp(0.0, 1.0),
+        )
+    }
+
+    // Returns the red, green and blue components as bytes
+    pub fn to_rgb8(self) -> (u8, u8, u8) {
+        (
+            (self.red * 255.0).round() as u8,
+            (self.green * 255.0).round() as u8,
+            (self.blue * 255.0).round() as u8,
+        )
+    }
+}
+
+pub const BACKGROUND: Colour = Colour::new(0.51, 0.51, 0.51); // Drawn for empty cells
+
+const WATER_SURFACE: Colour = Colour::new(0.40, 0.75, 1.00);
+const SHADE_VARIATION: f32 = 0.12; // Largest difference in brightness between two cells of the same type
+const MAX_SHADED_DEPTH: usize = 24; // Depth (in cells) below which liquids are drawn no darker
+const DEEPEST_SHADE: f32 = 0.55; // Brightness of liquids at the maximum depth, relative to their surface
+
+impl CellType {
+    pub fn colour(self) -> Colour {
+        match self {
+            CellType::Empty => BACKGROUND,
+            CellType::Wall => Colour::new(0.00, 0.00, 0.00),
+            CellType::Wood => Colour::new(0.50, 0.42, 0.31),
+            CellType::Sand => Colour::new(0.83, 0.69, 0.51),
+            CellType::Water => Colour::new(0.00, 0.47, 0.95),
+            CellType::Oil => Colour::new(0.30, 0.25, 0.18),
+            CellType::Steam => Colour::new(0.78, 0.78, 0.78),
+            CellType::Lava => Colour::new(0.90, 0.16, 0.22),
+            CellType::Stone => Colour::new(0.31, 0.31, 0.31),
+            CellType::Obsidian => Colour::new(0.44, 0.12, 0.49),
+            CellType::Fire => Colour::new(1.00, 0.63, 0.00),
+            CellType::Acid => Colour::new(0.00, 0.62, 0.18),
+            CellType::Glass => Colour::new(0.75, 0.90, 0.90),
+            CellType::Ice => Colour::new(0.65, 0.85, 1.00),
+            CellType::Snow => Colour::new(1.00, 1.00, 1.00),
+            CellType::Seed => Colour::new(1.00, 0.80, 0.00),
+            CellType::Plant => Colour::new(0.00, 0.89, 0.19),
+            CellType::Gunpowder => Colour::new(0.30, 0.30, 0.30),
+            CellType::Tnt => Colour::new(0.75, 0.13, 0.22),
+            CellType::Smoke => Colour::new(0.40, 0.40, 0.45),
+            CellType::ToxicGas => Colour::new(0.60, 0.80, 0.20),
+            CellType::Custom1 => Colour::new(0.90, 0.30, 0.80),
+            CellType::Custom2 => Colour::new(0.30, 0.80, 0.80),
+            CellType::Custom3 => Colour::new(0.95, 0.55, 0.35),
+            CellType::Custom4 => Colour::new(0.55, 0.45, 0.95),
+        }
+    }
+}
+
+// Returns the colour of a material in the world, which for custom materials may be given by their scripts
+#[cfg_attr(not(feature = "scripting"), allow(unused_variables))]
+pub fn material_colour(world: &World, cell_type: CellType) -> Colour {
+    #[cfg(feature = "scripting")]
+    if let Some([red, green, blue]) = world
+        .scripts
+        .as_ref()
+        .and_then(|scripts| scripts.colour(cell_type))
+    {
+        return Colour::new(red, green, blue);
+    }
+    cell_type.colour()
+}
+
+// Returns the colour to draw a cell in, which depends on its neighbours as well as its type (water at the surface is
+// drawn lighter) and is varied by the cell's shade, so that areas of one material are not drawn flat. With depth
+// shading, liquids are also drawn darker the further they are below the surface of a body of the same liquid.
+pub fn cell_colour(world: &World, row: usize, column: usize, depth_shading: bool) -> Colour {
+    let cell = world.grid.cells[row][column];
+    let cell_type = cell.cell_type;
+    if cell_type == CellType::Empty {
+        return BACKGROUND;
+    }
+    let mut colour = material_colour(world, cell_type);
+    if cell_type == CellType::Water
+        && (row == world.grid.rows - 1
+            || world.grid.cells[row + 1][column].cell_type == CellType::Empty)
+    {
+        colour = WATER_SURFACE;
+    }
+    let mut brightness = 1.0 + (cell.shade as f32 / 255.0 - 0.5) * SHADE_VARIATION;
+    if depth_shading && cell_type.is_liquid() {
+        let depth = (row + 1..world.grid.rows)
+            .take(MAX_SHADED_DEPTH)
+            .take_while(|&above| world.grid.cells[above][column].cell_type == cell_type)
+            .count();
+        brightness *= 1.0 - (1.0 - DEEPEST_SHADE) * depth as f32 / MAX_SHADED_DEPTH as f32;
+    }
+    colour.scaled(brightness)
+}
+
+// Writes the colour of every cell into the buffer as red, green, blue and alpha bytes, row by row starting with the top
+// row (the layout of images and textures), for renderers and exporters that work with whole images rather than cells
+pub fn write_rgba8(world: &World, depth_shading: bool, pixels: &mut [u8]) {
+    assert_eq!(pixels.len(), world.grid.rows * world.grid.columns * 4);
+    for (pixel_row, row) in pixels
+        .chunks_exact_mut(world.grid.columns * 4)
+        .zip((0..world.grid.rows).rev())
+    {
+        for (pixel, column) in pixel_row.chunks_exact_mut(4).zip(0..world.grid.columns) {
+            let (red, green, blue) = cell_colour(world, row, column, depth_shading).to_rgb8();
+            pixel.copy_from_slice(&[red, green, blue, 255]);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// This module tracks which parts of the world are active, so that regions that have settled can be skipped.
+//
+// The grid is divided into square chunks. A chunk is woken whenever one of its cells changes, or could change on a
+// later step without being disturbed (e.g. fire that has yet to burn out). Each step only the chunks that were woken
+// during the previous step, and the chunks next to them, are updated. Chunks that are left undisturbed go to sleep and
+// cost nothing to update until something wakes them again.
+
+use super::{new_shade, CellType, Gravity, World};
+
+pub const CHUNK_SIZE: usize = 32; // Chunk height and width (in number of cells)
+
+pub struct Chunks {
+    pub rows: usize,
+    pub columns: usize,
+    awake: Vec<bool>,  // Chunks woken during the current step
+    active: Vec<bool>, // Chunks being updated during the current step
+    gravity: Gravity,  // Direction of gravity the sleeping chunks settled under
+}
+
+impl Chunks {
+    pub fn new(rows: usize, columns: usize, gravity: Gravity) -> Self {
+        let rows = rows.div_ceil(CHUNK_SIZE);
+        let columns = columns.div_ceil(CHUNK_SIZE);
+        Chunks {
+            rows,
+            columns,
+            awake: vec![true; rows * columns],
+            active: vec![false; rows * columns],
+            gravity,
+        }
+    }
+
+    pub fn is_active(&self, chunk_row: usize, chunk_column: usize) -> bool {
+        self.active[chunk_row * self.columns + chunk_column]
+    }
+
+    pub fn is_awake(&self, chunk_row: usize, chunk_column: usize) -> bool {
+        self.awake[chunk_row * self.columns + chunk_column]
+    }
+
+    // Activates the chunks that were woken during the previous step and the chunks next to them, and puts all chunks to
+    // sleep until they are woken again
+    fn begin_step(&mut self) {
+        for chunk_row in 0..self.rows {
+            for chunk_column in 0..self.columns {
+                let mut active = false;
+                for neighbour_row in
+                    chunk_row.saturating_sub(1)..=(chunk_row + 1).min(self.rows - 1)
+                {
+                    for neighbour_column in
+                        chunk_column.saturating_sub(1)..=(chunk_column + 1).min(self.columns - 1)
+                    {
+                        active |= self.is_awake(neighbour_row, neighbour_column);
+                    }
+                }
+                self.active[chunk_row * self.columns + chunk_column] = active;
+            }
+        }
+        self.awake.fill(false);
+    }
+}
+
+impl World {
+    // Updates the active chunks, from the bottom up (relative to gravity). Each run of neighbouring active chunks in a
+    // layer of chunks is updated as a single block, so that when every chunk is active the grid is swept exactly as if
+    // it were updated in one block.
+    pub(super) fn update_chunks(&mut self) {
+        // Cells that have settled under one direction of gravity will not stay settled under another
+        if self.gravity != self.chunks.gravity {
+            self.chunks.gravity = self.gravity;
+            self.wake_all();
+        }
+        self.chunks.begin_step();
+        let sideways = matches!(self.gravity, Gravity::Left | Gravity::Right);
+        let (bands, length, band_cells, length_cells) = if sideways {
+            (
+                self.chunks.columns,
+                self.chunks.rows,
+                self.grid.columns,
+                self.grid.rows,
+            )
+        } else {
+            (
+                self.chunks.rows,
+                self.chunks.columns,
+                self.grid.rows,
+                self.grid.columns,
+            )
+        };
+        let is_active = |chunks: &Chunks, band: usize, index: usize| {
+            if sideways {
+                chunks.is_active(index, band)
+            } else {
+                chunks.is_active(band, index)
+            }
+        };
+        for band in 0..bands {
+            let band = if matches!(self.gravity, Gravity::Up | Gravity::Right) {
+                bands - 1 - band
+            } else {
+                band
+            };
+            let start_layer = band * CHUNK_SIZE;
+            let layers = CHUNK_SIZE.min(band_cells - start_layer);
+            let mut index = 0;
+            while index < length {
+                if !is_active(&self.chunks, band, index) {
+                    index += 1;
+                    continue;
+                }
+                let start = index;
+                while index < length && is_active(&self.chunks, band, index) {
+                    index += 1;
+                }
+                let start_cell = start * CHUNK_SIZE;
+                let cells = (index * CHUNK_SIZE).min(length_cells) - start_cell;
+                if sideways {
+                    self.update_block(start_layer, start_cell, layers, cells);
+                } else {
+                    self.update_block(start_cell, start_layer, cells, layers);
+                }
+            }
+        }
+    }
+
+    // Wakes the chunk containing the given cell, so that it is updated in the next step
+    pub(super) fn wake(&mut self, row: usize, column: usize) {
+        let index = (row / CHUNK_SIZE) * self.chunks.columns + column / CHUNK_SIZE;
+        self.chunks.awake[index] = true;
+    }
+
+    // Marks a cell as changed in the current step, so that it is not updated again and its chunk is woken
+    pub(super) fn touch(&mut self, row: usize, column: usize) {
+        self.grid.cells[row][column].stamp = self.stamp;
+        self.wake(row, column);
+    }
+
+    // Wakes every chunk, which is needed after changing the grid without going through the world (e.g. after writing to
+    // the cells directly)
+    pub fn wake_all(&mut self) {
+        self.chunks.awake.fill(true);
+    }
+
+    // Sets a cell's type, leaving it at rest and giving it a new shade if the type changes (so that painting over cells
+    // does not make them flicker)
+    pub fn set_cell(&mut self, row: usize, column: usize, cell_type: CellType) {
+        let cell = &mut self.grid.cells[row][column];
+        if cell.cell_type != cell_type {
+            cell.shade = new_shade(row, column, self.steps);
+        }
+        cell.reset(cell_type);
+        self.wake(row, column);
+    }
+}
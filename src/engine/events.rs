@@ -0,0 +1,100 @@
+// This module defines events, which modify an area of the world in one step (unlike the cell by cell updates made while
+// sweeping the grid).
+//
+// Events are queued during a step and processed once the sweep has finished. Events queued while processing events
+// (e.g. explosions chaining to nearby explosives) are processed in the following step.
+
+use super::{CellType, World};
+
+const FLASH_PROBABILITY: f32 = 0.2; // Chance of a cleared cell being left on fire
+const DEBRIS_PROBABILITY: f32 = 0.5; // Chance of a cleared powder or liquid cell being thrown clear of the blast
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Event {
+    Explosion {
+        row: usize,
+        column: usize,
+        radius: usize,
+    },
+}
+
+impl World {
+    pub(super) fn process_events(&mut self) {
+        for event in std::mem::take(&mut self.events) {
+            match event {
+                Event::Explosion {
+                    row,
+                    column,
+                    radius,
+                } => self.explode(row, column, radius),
+            }
+        }
+    }
+
+    // Clears all non-wall cells within the radius, throwing some of the debris clear of the blast and chaining to any
+    // explosives caught in it
+    fn explode(&mut self, row: usize, column: usize, radius: usize) {
+        let start_row = row.saturating_sub(radius);
+        let end_row = (row + radius).min(self.grid.rows - 1);
+        let start_column = column.saturating_sub(radius);
+        let end_column = (column + radius).min(self.grid.columns - 1);
+        for blast_row in start_row..=end_row {
+            for blast_column in start_column..=end_column {
+                let row_offset = blast_row as f32 - row as f32;
+                let column_offset = blast_column as f32 - column as f32;
+                let distance = (row_offset * row_offset + column_offset * column_offset).sqrt();
+                if distance > radius as f32 {
+                    continue;
+                }
+                let cell_type = self.grid.cells[blast_row][blast_column].cell_type;
+                if cell_type == CellType::Wall {
+                    continue;
+                }
+                if let Some(blast_radius) = cell_type.blast_radius() {
+                    if (blast_row, blast_column) != (row, column) {
+                        self.events.push(Event::Explosion {
+                            row: blast_row,
+                            column: blast_column,
+                            radius: blast_radius,
+                        });
+                    }
+                } else if cell_type.density().is_some()
+                    && cell_type != CellType::Empty
+                    && cell_type != CellType::Fire
+                    && distance > 0.0
+                    && self.random::<f32>() < DEBRIS_PROBABILITY
+                {
+                    // Throw the debris outwards along the line from the centre of the blast
+                    let throw = (radius as f32
+                        + self.random_range(1.0..=(radius as f32 / 2.0 + 1.0)))
+                        / distance;
+                    let debris_row = row as f32 + row_offset * throw;
+                    let debris_column = column as f32 + column_offset * throw;
+                    if debris_row >= 0.0
+                        && debris_column >= 0.0
+                        && (debris_row as usize) < self.grid.rows
+                        && (debris_column as usize) < self.grid.columns
+                    {
+                        let (debris_row, debris_column) =
+                            (debris_row as usize, debris_column as usize);
+                        if self.grid.cells[debris_row][debris_column].cell_type == CellType::Empty {
+                            // The debris keeps the shade of the cell it was thrown from
+                            let shade = self.grid.cells[blast_row][blast_column].shade;
+                            let debris = &mut self.grid.cells[debris_row][debris_column];
+                            debris.reset(cell_type);
+                            debris.shade = shade;
+                            self.touch(debris_row, debris_column);
+                        }
+                    }
+                }
+                let blast_type = if self.random::<f32>() < FLASH_PROBABILITY {
+                    CellType::Fire
+                } else {
+                    CellType::Empty
+                };
+                self.grid.cells[blast_row][blast_column].reset(blast_type);
+                self.touch(blast_row, blast_column);
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// This module defines the behaviour of gases.
+//
+// A gas is described by:
+//     - Its density relative to air (empty cells), gases lighter than air rise and gases heavier than air sink
+//       (relative to the direction of gravity), and a lighter gas is displaced upwards by a heavier one;
+//     - Its dispersion rate, the chance per step of drifting sideways (mixing with other gases) when it cannot rise or
+//       sink;
+//     - Its lifetime, the mean number of steps before it dissipates, after which it becomes the cell type it decays
+//       into.
+
+use super::{CellType, World};
+
+const AIR_DENSITY: f32 = 1.0;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Gas {
+    pub density: f32,
+    pub dispersion: f32,
+    pub lifetime: f32,
+    pub decays_into: CellType,
+}
+
+impl CellType {
+    pub fn gas(self) -> Option<Gas> {
+        match self {
+            CellType::Steam => Some(Gas {
+                density: 0.6,
+                dispersion: 1.0,
+                lifetime: 1000.0,
+                decays_into: CellType::Water,
+            }),
+            CellType::Smoke => Some(Gas {
+                density: 0.8,
+                dispersion: 0.5,
+                lifetime: 300.0,
+                decays_into: CellType::Empty,
+            }),
+            CellType::ToxicGas => Some(Gas {
+                density: 1.5,
+                dispersion: 0.8,
+                lifetime: 600.0,
+                decays_into: CellType::Empty,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl World {
+    pub(super) fn update_gas(&mut self, row: usize, column: usize) {
+        let cell_type = self.grid.cells[row][column].cell_type;
+        let gas = match cell_type.gas() {
+            Some(gas) => gas,
+            None => return,
+        };
+        // Randomly dissipate, keeping the chunk awake until the gas has gone
+        if self.random::<f32>() < 1.0 / gas.lifetime {
+            self.grid.cells[row][column].reset(gas.decays_into);
+            self.touch(row, column);
+            return;
+        }
+        self.wake(row, column);
+        // Rise (or sink) into air or a gas that is heavier (or lighter), either straight up (or down) or diagonally
+        let rising = gas.density < AIR_DENSITY;
+        let direction = if rising {
+            -1
+        } else if gas.density > AIR_DENSITY {
+            1
+        } else {
+            0
+        };
+        let can_displace = |other: CellType| {
+            other == CellType::Empty
+                || other.gas().is_some_and(|other_gas| {
+                    if rising {
+                        other_gas.density > gas.density
+                    } else {
+                        other_gas.density < gas.density
+                    }
+                })
+        };
+        if direction != 0 {
+            if let Some((next_row, next_column)) = self.relative(row, column, direction, 0) {
+                if can_displace(self.grid.cells[next_row][next_column].cell_type) {
+                    self.swap(row, column, next_row, next_column);
+                    return;
+                }
+            }
+            if let Some((side_row, side_column)) =
+                self.choose_across(row, column, direction, can_displace)
+            {
+                self.swap(row, column, side_row, side_column);
+                return;
+            }
+        }
+        // Otherwise drift sideways into air or another gas
+        if self.random::<f32>() < gas.dispersion {
+            let can_mix = |other: CellType| {
+                other == CellType::Empty || (other != cell_type && other.gas().is_some())
+            };
+            if let Some((side_row, side_column)) = self.choose_across(row, column, 0, can_mix) {
+                self.swap(row, column, side_row, side_column);
+            }
+        }
+    }
+}
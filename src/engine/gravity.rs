@@ -0,0 +1,130 @@
+// This module defines the direction of gravity, and the frame of reference that material rules use to respect it.
+//
+// Rules refer to cells relative to the direction of gravity rather than by row and column, where:
+//     - 'down' is towards gravity (a positive offset) and 'up' is away from it (a negative offset);
+//     - 'across' is perpendicular to gravity, with the two sides given by offsets of -1 and +1.
+//
+// With gravity switched off there is no down or up (so nothing falls, sinks or rises), and moving across picks a random
+// axis each time, so liquids and gases drift in all directions.
+
+use super::{CellType, World};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Gravity {
+    Down,
+    Up,
+    Left,
+    Right,
+    Off,
+}
+
+impl Gravity {
+    // Returns the (row, column) steps for moving one cell down and one cell across
+    fn axes(self) -> ((isize, isize), (isize, isize)) {
+        match self {
+            Gravity::Down | Gravity::Off => ((-1, 0), (0, 1)),
+            Gravity::Up => ((1, 0), (0, 1)),
+            Gravity::Left => ((0, -1), (1, 0)),
+            Gravity::Right => ((0, 1), (1, 0)),
+        }
+    }
+}
+
+impl World {
+    // Returns the position of the cell the given number of cells down and across from the given cell, or None if it
+    // is outside the grid (or there is no down because gravity is off)
+    pub(super) fn relative(
+        &self,
+        row: usize,
+        column: usize,
+        down: isize,
+        across: isize,
+    ) -> Option<(usize, usize)> {
+        if self.gravity == Gravity::Off && down != 0 {
+            return None;
+        }
+        let ((down_row, down_column), (across_row, across_column)) = self.gravity.axes();
+        self.position(
+            row as isize + down * down_row + across * across_row,
+            column as isize + down * down_column + across * across_column,
+        )
+    }
+
+    pub(super) fn position(&self, row: isize, column: isize) -> Option<(usize, usize)> {
+        if row >= 0
+            && column >= 0
+            && (row as usize) < self.grid.rows
+            && (column as usize) < self.grid.columns
+        {
+            Some((row as usize, column as usize))
+        } else {
+            None
+        }
+    }
+
+    // Returns the position of the cell to either side of the cell the given number of cells down (or up) from the given
+    // cell whose type satisfies the condition, choosing randomly if both do
+    pub(super) fn choose_across<F>(
+        &mut self,
+        row: usize,
+        column: usize,
+        down: isize,
+        condition: F,
+    ) -> Option<(usize, usize)>
+    where
+        F: Fn(CellType) -> bool,
+    {
+        self.choose_position(row, column, down, |world, (side_row, side_column)| {
+            condition(world.grid.cells[side_row][side_column].cell_type)
+        })
+    }
+
+    // Returns the position of the cell to either side of the cell the given number of cells down (or up) from the given
+    // cell that satisfies the condition (given the world, as this borrows it mutably to make the choice), choosing
+    // randomly if both do
+    pub(super) fn choose_position<F>(
+        &mut self,
+        row: usize,
+        column: usize,
+        down: isize,
+        condition: F,
+    ) -> Option<(usize, usize)>
+    where
+        F: Fn(&World, (usize, usize)) -> bool,
+    {
+        let (left, right) = if self.gravity == Gravity::Off {
+            if down != 0 {
+                return None;
+            }
+            if self.random() {
+                (
+                    self.position(row as isize, column as isize - 1),
+                    self.position(row as isize, column as isize + 1),
+                )
+            } else {
+                (
+                    self.position(row as isize - 1, column as isize),
+                    self.position(row as isize + 1, column as isize),
+                )
+            }
+        } else {
+            (
+                self.relative(row, column, down, -1),
+                self.relative(row, column, down, 1),
+            )
+        };
+        let left = left.filter(|&position| condition(self, position));
+        let right = right.filter(|&position| condition(self, position));
+        match (left, right) {
+            (Some(_), Some(_)) => {
+                if self.random() {
+                    right
+                } else {
+                    left
+                }
+            }
+            (Some(_), None) => left,
+            (None, _) => right,
+        }
+    }
+}
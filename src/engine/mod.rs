@@ -1,7 +1,41 @@
+mod chunks;
+mod events;
+mod gases;
+mod gravity;
+mod momentum;
+mod reactions;
+mod rules;
+#[cfg(feature = "scripting")]
+mod scripts;
+#[cfg(test)]
+mod tests;
+mod text;
+
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-#[derive(Copy, Clone, PartialEq)]
+pub use chunks::{Chunks, CHUNK_SIZE};
+pub use events::Event;
+pub use gravity::Gravity;
+use rand::distributions::uniform::{SampleRange, SampleUniform};
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+pub use reactions::{Reaction, IMMUNE, REACTIONS};
+pub use rules::Rules;
+#[cfg(feature = "scripting")]
+pub use scripts::Scripts;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const LAVA_FLOW_PROBABILITY: f32 = 0.2; // Per step, lava is viscous so only moves occasionally
+const FIRE_BURN_OUT_PROBABILITY: f32 = 0.02; // Per step
+const SEED_SPROUT_PROBABILITY: f32 = 0.01; // Per step, when resting on sand next to water
+const PLANT_GROWTH_PROBABILITY: f32 = 0.05; // Per step, for each plant cell next to water
+const PLANT_BRANCH_PROBABILITY: f32 = 0.15; // Per growth
+
+#[derive(Copy, Clone, PartialEq, Debug)]
 #[repr(u8)]
 pub enum CellType {
     Empty = 0,
@@ -11,12 +45,179 @@ pub enum CellType {
     Water = 4,
     Oil = 5,
     Steam = 6,
+    Lava = 7,
+    Stone = 8,
+    Obsidian = 9,
+    Fire = 10,
+    Acid = 11,
+    Glass = 12,
+    Ice = 13,
+    Snow = 14,
+    Seed = 15,
+    Plant = 16,
+    Gunpowder = 17,
+    Tnt = 18,
+    Smoke = 19,
+    ToxicGas = 20,
+    Custom1 = 21, // The custom materials do nothing unless given a script (see the scripts module)
+    Custom2 = 22,
+    Custom3 = 23,
+    Custom4 = 24,
 }
 
-#[derive(Clone)]
+impl CellType {
+    // Every cell type, in order of their codes
+    pub const ALL: [CellType; 25] = [
+        CellType::Empty,
+        CellType::Wall,
+        CellType::Wood,
+        CellType::Sand,
+        CellType::Water,
+        CellType::Oil,
+        CellType::Steam,
+        CellType::Lava,
+        CellType::Stone,
+        CellType::Obsidian,
+        CellType::Fire,
+        CellType::Acid,
+        CellType::Glass,
+        CellType::Ice,
+        CellType::Snow,
+        CellType::Seed,
+        CellType::Plant,
+        CellType::Gunpowder,
+        CellType::Tnt,
+        CellType::Smoke,
+        CellType::ToxicGas,
+        CellType::Custom1,
+        CellType::Custom2,
+        CellType::Custom3,
+        CellType::Custom4,
+    ];
+
+    // The cell types whose behaviour can be defined by scripts
+    pub const CUSTOM: [CellType; 4] = [
+        CellType::Custom1,
+        CellType::Custom2,
+        CellType::Custom3,
+        CellType::Custom4,
+    ];
+
+    // Returns the cell type with the given code (see the examples module), treating unknown codes as empty
+    pub fn from_u8(code: u8) -> CellType {
+        CellType::ALL
+            .get(code as usize)
+            .copied()
+            .unwrap_or(CellType::Empty)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CellType::Empty => "Empty",
+            CellType::Wall => "Wall",
+            CellType::Wood => "Wood",
+            CellType::Sand => "Sand",
+            CellType::Water => "Water",
+            CellType::Oil => "Oil",
+            CellType::Steam => "Steam",
+            CellType::Lava => "Lava",
+            CellType::Stone => "Stone",
+            CellType::Obsidian => "Obsidian",
+            CellType::Fire => "Fire",
+            CellType::Acid => "Acid",
+            CellType::Glass => "Glass",
+            CellType::Ice => "Ice",
+            CellType::Snow => "Snow",
+            CellType::Seed => "Seed",
+            CellType::Plant => "Plant",
+            CellType::Gunpowder => "Gunpowder",
+            CellType::Tnt => "TNT",
+            CellType::Smoke => "Smoke",
+            CellType::ToxicGas => "Toxic gas",
+            CellType::Custom1 => "Custom 1",
+            CellType::Custom2 => "Custom 2",
+            CellType::Custom3 => "Custom 3",
+            CellType::Custom4 => "Custom 4",
+        }
+    }
+
+    // Relative density, used to decide which cells a falling or flowing cell can displace (None for static cells)
+    pub fn density(self) -> Option<u8> {
+        match self {
+            CellType::Empty | CellType::Fire => Some(0),
+            CellType::Steam | CellType::Smoke | CellType::ToxicGas => Some(10),
+            CellType::Oil => Some(20),
+            CellType::Snow => Some(25),
+            CellType::Water | CellType::Acid => Some(30),
+            CellType::Seed => Some(35),
+            CellType::Lava => Some(40),
+            CellType::Gunpowder => Some(45),
+            CellType::Sand => Some(50),
+            CellType::Wall
+            | CellType::Wood
+            | CellType::Stone
+            | CellType::Obsidian
+            | CellType::Glass
+            | CellType::Ice
+            | CellType::Plant
+            | CellType::Tnt
+            | CellType::Custom1
+            | CellType::Custom2
+            | CellType::Custom3
+            | CellType::Custom4 => None,
+        }
+    }
+
+    fn can_displace(self, other: CellType) -> bool {
+        match (self.density(), other.density()) {
+            (Some(density), Some(other_density)) => density > other_density,
+            _ => false,
+        }
+    }
+
+    // Radius of the explosion set off when the cell touches fire (None for cells that are not explosive)
+    fn blast_radius(self) -> Option<usize> {
+        match self {
+            CellType::Gunpowder => Some(3),
+            CellType::Tnt => Some(6),
+            _ => None,
+        }
+    }
+
+    pub fn is_liquid(self) -> bool {
+        matches!(
+            self,
+            CellType::Water | CellType::Oil | CellType::Lava | CellType::Acid
+        )
+    }
+}
+
+// Cells are packed into five bytes, so that as much of the grid as possible fits in the cache
+#[derive(Copy, Clone)]
 pub struct Cell {
     pub cell_type: CellType,
-    updated: bool,
+    pub shade: u8, // Random variation in the cell's colour, given when it is created and carried with it as it moves
+    stamp: u8,     // Stamp of the step in which the cell last changed (see World::update)
+    velocity_x: i8, // In sixteenths of a cell per step, across the direction of gravity
+    velocity_y: i8, // In sixteenths of a cell per step, positive against the direction of gravity
+}
+
+impl Cell {
+    // Changes the cell's type, leaving it at rest, so that a new cell does not move off with the old one's velocity
+    fn reset(&mut self, cell_type: CellType) {
+        self.cell_type = cell_type;
+        self.velocity_x = 0;
+        self.velocity_y = 0;
+    }
+}
+
+// Returns the shade of a cell created at the given position and step. This is a hash rather than drawn from the world's
+// random number generator, so that painting cells does not change how a seeded world runs.
+fn new_shade(row: usize, column: usize, step: u32) -> u8 {
+    let mut hash = ((row as u64) << 40) ^ ((column as u64) << 20) ^ step as u64;
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (hash ^ (hash >> 31)) as u8
 }
 
 pub struct Grid {
@@ -26,656 +227,491 @@ pub struct Grid {
 }
 
 impl Grid {
-    pub fn new(world_size: usize) -> Self {
+    pub fn new(rows: usize, columns: usize) -> Self {
         Grid {
-            rows: world_size,
-            columns: world_size,
-            cells: vec![
-                vec![
-                    Cell {
-                        cell_type: CellType::Empty,
-                        updated: false
-                    };
-                    world_size
-                ];
-                world_size
-            ],
+            rows,
+            columns,
+            cells: (0..rows)
+                .map(|row| {
+                    (0..columns)
+                        .map(|column| Cell {
+                            cell_type: CellType::Empty,
+                            shade: new_shade(row, column, 0),
+                            stamp: 0,
+                            velocity_x: 0,
+                            velocity_y: 0,
+                        })
+                        .collect()
+                })
+                .collect(),
         }
     }
 }
 
 pub struct World {
     pub steps: u32,
-    pub world_size: usize, // World height and width (in number of cells)
+    pub world_size: usize, // World height and width (in number of cells), or the larger of the two if they differ
     pub grid: Grid,
+    pub chunks: Chunks,
+    pub reactions: Vec<Reaction>,
+    pub immune: Vec<CellType>, // Cell types that are never changed by a neighbouring cell's reaction
+    pub events: Vec<Event>,    // Events to be processed at the end of the current step
+    pub momentum: bool, // Whether falling cells accelerate and liquids splash (off by default)
+    pub gravity: Gravity,
+    pub gravity_strength: f32, // Scales how quickly falling cells accelerate (when momentum is enabled)
+    pub rules: Option<Arc<Rules>>, // Behaviour of materials given by rules rather than built in (see the rules module)
+    #[cfg(feature = "scripting")]
+    pub scripts: Option<Arc<Scripts>>, // Behaviour of the custom materials, shared by the worlds a frontend creates
+    stamp: u8, // Stamp of the current step, given to every cell that changes during it
+    reactions_by_type: Vec<Vec<usize>>, // Indices of the reactions of each cell type, in order of their codes
+    rng: StdRng, // Source of all randomness in the simulation, so that it can be seeded
 }
 
 impl World {
     pub fn new(world_size: usize) -> Self {
+        World::with_size(world_size, world_size)
+    }
+
+    // Creates a world that is not necessarily square
+    pub fn with_size(rows: usize, columns: usize) -> Self {
         World {
             steps: 0,
-            world_size: world_size,
-            grid: Grid::new(world_size),
+            world_size: rows.max(columns),
+            grid: Grid::new(rows, columns),
+            chunks: Chunks::new(rows, columns, Gravity::Down),
+            reactions: REACTIONS.to_vec(),
+            immune: IMMUNE.to_vec(),
+            events: Vec::new(),
+            momentum: false,
+            gravity: Gravity::Down,
+            gravity_strength: 1.0,
+            rules: None,
+            #[cfg(feature = "scripting")]
+            scripts: None,
+            stamp: 0,
+            reactions_by_type: vec![Vec::new(); CellType::ALL.len()],
+            rng: entropy_rng(),
         }
     }
 
-    #[cfg(feature = "parallel")]
+    // Reseeds the world's random number generator, so that the simulation runs the same way every time from this point
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     pub fn update(&mut self) {
         // Increment simulation step count
         self.steps += 1;
-        // Todo: Implement a solution that calls this function on multiple blocks in parallel
-        self.update_block(0, 0, self.grid.columns, self.grid.rows);
-        // Reset updated status to false
+        // Move on to the next stamp, so that no cell counts as updated in this step yet. Stamp 0 is never used for a
+        // step, and when the stamps wrap around every cell is reset to it, so that a cell that has not changed for 255
+        // steps is not mistaken for one updated in this step.
+        self.stamp = self.stamp.wrapping_add(1);
+        if self.stamp == 0 {
+            self.reset_stamps();
+            self.stamp = 1;
+        }
+        self.index_reactions();
+        // Todo: Implement a solution that calls update_block on multiple blocks in parallel
+        self.update_chunks();
+        self.process_events();
+    }
+
+    // Finds the reactions of each cell type, so that cells without any (most of them) skip reacting altogether. This is
+    // redone every step, as the reaction table can be changed between steps.
+    fn index_reactions(&mut self) {
+        for indices in &mut self.reactions_by_type {
+            indices.clear();
+        }
+        for (index, reaction) in self.reactions.iter().enumerate() {
+            self.reactions_by_type[reaction.cell as usize].push(index);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn reset_stamps(&mut self) {
         self.grid.cells.par_iter_mut().for_each(|n| {
             for element in n {
-                element.updated = false;
+                element.stamp = 0;
             }
         });
     }
 
     #[cfg(not(feature = "parallel"))]
-    pub fn update(&mut self) {
-        // Increment simulation step count
-        self.steps += 1;
-        self.update_block(0, 0, self.grid.columns, self.grid.rows);
-        // Reset updated status to false
+    fn reset_stamps(&mut self) {
         for row in 0..self.grid.rows {
             for column in 0..self.grid.columns {
-                self.grid.cells[row][column].updated = false;
+                self.grid.cells[row][column].stamp = 0;
             }
         }
     }
 
     fn update_block(&mut self, start_column: usize, start_row: usize, columns: usize, rows: usize) {
-        // Update a 'block' of world grid cells (either a subset of the world, or the entire world)
-        for row in start_row..(start_row + rows) {
-            for column in start_column..(start_column + columns) {
-                let cell = self.grid.cells[row][column].clone();
-                if self.grid.cells[row][column].updated == false {
-                    if cell.cell_type == CellType::Sand {
-                        // If the row index is not 0, i.e. it is not the bottom row
-                        if row > 0 {
-                            if self.grid.cells[row - 1][column].cell_type != CellType::Sand
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Wall
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Wood
-                            {
-                                // If the cell below is water or oil, displace the water or oil
-                                if self.grid.cells[row - 1][column].cell_type == CellType::Water
-                                    || self.grid.cells[row - 1][column].cell_type == CellType::Oil
-                                {
-                                    let mut up_left = false;
-                                    let mut up_right = false;
-                                    if column > 0 {
-                                        if self.grid.cells[row][column - 1].cell_type
-                                            == CellType::Empty
-                                        {
-                                            up_left = true;
-                                        }
-                                    }
-                                    if column < self.grid.columns - 1 {
-                                        if self.grid.cells[row][column + 1].cell_type
-                                            == CellType::Empty
-                                        {
-                                            up_right = true;
-                                        }
-                                    }
-                                    if up_left && up_right {
-                                        if rand::random() {
-                                            up_left = false;
-                                        } else {
-                                            up_right = false;
-                                        }
-                                    }
-                                    if up_left {
-                                        self.grid.cells[row][column - 1].cell_type =
-                                            self.grid.cells[row - 1][column].cell_type;
-                                        self.grid.cells[row - 1][column].cell_type = CellType::Sand;
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row][column - 1].updated = true;
-                                        self.grid.cells[row - 1][column].updated = true;
-                                    } else if up_right {
-                                        self.grid.cells[row][column + 1].cell_type =
-                                            self.grid.cells[row - 1][column].cell_type;
-                                        self.grid.cells[row - 1][column].cell_type = CellType::Sand;
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row][column + 1].updated = true;
-                                        self.grid.cells[row - 1][column].updated = true;
-                                    } else {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row - 1][column].cell_type;
-                                        self.grid.cells[row - 1][column].cell_type = CellType::Sand;
-                                        self.grid.cells[row - 1][column].updated = true;
-                                    }
-                                } else {
-                                    self.grid.cells[row][column].cell_type =
-                                        self.grid.cells[row - 1][column].cell_type;
-                                    self.grid.cells[row - 1][column].cell_type = CellType::Sand;
-                                    self.grid.cells[row - 1][column].updated = true;
-                                }
-                            } else {
-                                let mut down_left = false;
-                                let mut down_right = false;
-                                if column > 0 {
-                                    if self.grid.cells[row - 1][column - 1].cell_type
-                                        != CellType::Sand
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        down_left = true;
-                                    }
-                                }
-                                if column < self.grid.columns - 1 {
-                                    if self.grid.cells[row - 1][column + 1].cell_type
-                                        != CellType::Sand
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        down_right = true;
-                                    }
-                                }
-                                if down_left && down_right {
-                                    if rand::random() {
-                                        down_left = false;
-                                    } else {
-                                        down_right = false;
-                                    }
-                                }
-                                if down_left {
-                                    self.grid.cells[row][column].cell_type =
-                                        self.grid.cells[row - 1][column - 1].cell_type;
-                                    self.grid.cells[row - 1][column - 1].cell_type = CellType::Sand;
-                                    self.grid.cells[row - 1][column - 1].updated = true;
-                                } else if down_right {
-                                    self.grid.cells[row][column].cell_type =
-                                        self.grid.cells[row - 1][column + 1].cell_type;
-                                    self.grid.cells[row - 1][column + 1].cell_type = CellType::Sand;
-                                    self.grid.cells[row - 1][column + 1].updated = true;
-                                }
-                            }
-                        }
-                    } else if cell.cell_type == CellType::Water {
-                        // If the row index is not 0, i.e. it is not the bottom row
-                        if row > 0 {
-                            if self.grid.cells[row - 1][column].cell_type != CellType::Sand
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Wall
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Water
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Wood
-                            {
-                                // If the cell below is oil, displace the oil
-                                if self.grid.cells[row - 1][column].cell_type == CellType::Oil {
-                                    let mut up_left = false;
-                                    let mut up_right = false;
-                                    if column > 0 {
-                                        if self.grid.cells[row][column - 1].cell_type
-                                            == CellType::Empty
-                                        {
-                                            up_left = true;
-                                        }
-                                    }
-                                    if column < self.grid.columns - 1 {
-                                        if self.grid.cells[row][column + 1].cell_type
-                                            == CellType::Empty
-                                        {
-                                            up_right = true;
-                                        }
-                                    }
-                                    if up_left && up_right {
-                                        if rand::random() {
-                                            up_left = false;
-                                        } else {
-                                            up_right = false;
-                                        }
-                                    }
-                                    if up_left {
-                                        self.grid.cells[row][column - 1].cell_type =
-                                            self.grid.cells[row - 1][column].cell_type;
-                                        self.grid.cells[row - 1][column].cell_type =
-                                            CellType::Water;
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row][column - 1].updated = true;
-                                        self.grid.cells[row - 1][column].updated = true;
-                                    } else if up_right {
-                                        self.grid.cells[row][column + 1].cell_type =
-                                            self.grid.cells[row - 1][column].cell_type;
-                                        self.grid.cells[row - 1][column].cell_type =
-                                            CellType::Water;
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row][column + 1].updated = true;
-                                        self.grid.cells[row - 1][column].updated = true;
-                                    } else {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row - 1][column].cell_type;
-                                        self.grid.cells[row - 1][column].cell_type =
-                                            CellType::Water;
-                                        self.grid.cells[row - 1][column].updated = true;
-                                    }
-                                } else {
-                                    self.grid.cells[row][column].cell_type =
-                                        self.grid.cells[row - 1][column].cell_type;
-                                    self.grid.cells[row - 1][column].cell_type = CellType::Water;
-                                    self.grid.cells[row - 1][column].updated = true;
-                                }
-                            } else {
-                                let mut down_left = false;
-                                let mut down_right = false;
-                                let mut left = false;
-                                let mut right = false;
-                                if column > 0 {
-                                    if self.grid.cells[row - 1][column - 1].cell_type
-                                        != CellType::Sand
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        down_left = true;
-                                    }
-                                    if self.grid.cells[row][column - 1].cell_type != CellType::Sand
-                                        && self.grid.cells[row][column - 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row][column - 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row][column - 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        left = true;
-                                    }
-                                }
-                                if column < self.grid.columns - 1 {
-                                    if self.grid.cells[row - 1][column + 1].cell_type
-                                        != CellType::Sand
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        down_right = true;
-                                    }
-                                    if self.grid.cells[row][column + 1].cell_type != CellType::Sand
-                                        && self.grid.cells[row][column + 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row][column + 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row][column + 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        right = true;
-                                    }
-                                }
-                                if down_left && down_right {
-                                    if rand::random() {
-                                        down_left = false;
-                                    } else {
-                                        down_right = false;
-                                    }
-                                }
-                                if left && right {
-                                    if rand::random() {
-                                        left = false;
-                                    } else {
-                                        right = false;
-                                    }
-                                }
-                                if down_left || down_right {
-                                    if down_left {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row - 1][column - 1].cell_type;
-                                        self.grid.cells[row - 1][column - 1].cell_type =
-                                            CellType::Water;
-                                        self.grid.cells[row - 1][column - 1].updated = true;
-                                    } else if down_right {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row - 1][column + 1].cell_type;
-                                        self.grid.cells[row - 1][column + 1].cell_type =
-                                            CellType::Water;
-                                        self.grid.cells[row - 1][column + 1].updated = true;
-                                    }
-                                } else {
-                                    if right {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row][column + 1].cell_type;
-                                        self.grid.cells[row][column + 1].cell_type =
-                                            CellType::Water;
-                                        self.grid.cells[row][column + 1].updated = true;
-                                    } else if left {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row][column - 1].cell_type;
-                                        self.grid.cells[row][column - 1].cell_type =
-                                            CellType::Water;
-                                        self.grid.cells[row][column - 1].updated = true;
-                                    }
-                                }
-                            }
-                        }
-                        // Otherwise if the row index is 0, i.e. it is the bottom row
-                        else {
-                            let mut left = false;
-                            let mut right = false;
-                            if column > 0 {
-                                if self.grid.cells[row][column - 1].cell_type != CellType::Sand
-                                    && self.grid.cells[row][column - 1].cell_type != CellType::Wall
-                                    && self.grid.cells[row][column - 1].cell_type != CellType::Water
-                                    && self.grid.cells[row][column - 1].cell_type != CellType::Wood
-                                {
-                                    left = true;
-                                }
-                            }
-                            if column < self.grid.columns - 1 {
-                                if self.grid.cells[row][column + 1].cell_type != CellType::Sand
-                                    && self.grid.cells[row][column + 1].cell_type != CellType::Wall
-                                    && self.grid.cells[row][column + 1].cell_type != CellType::Water
-                                    && self.grid.cells[row][column + 1].cell_type != CellType::Wood
-                                {
-                                    right = true;
-                                }
-                            }
-                            if left && right {
-                                if rand::random() {
-                                    left = false;
-                                } else {
-                                    right = false;
-                                }
-                            }
-                            if right {
-                                self.grid.cells[row][column].cell_type =
-                                    self.grid.cells[row][column + 1].cell_type;
-                                self.grid.cells[row][column + 1].cell_type = CellType::Water;
-                                self.grid.cells[row][column + 1].updated = true;
-                            } else if left {
-                                self.grid.cells[row][column].cell_type =
-                                    self.grid.cells[row][column - 1].cell_type;
-                                self.grid.cells[row][column - 1].cell_type = CellType::Water;
-                                self.grid.cells[row][column - 1].updated = true;
-                            }
-                        }
-                    } else if cell.cell_type == CellType::Oil {
-                        // If the row index is not 0, i.e. it is not the bottom row
-                        if row > 0 {
-                            if self.grid.cells[row - 1][column].cell_type != CellType::Sand
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Wall
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Water
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Oil
-                                && self.grid.cells[row - 1][column].cell_type != CellType::Wood
-                            {
-                                self.grid.cells[row][column].cell_type =
-                                    self.grid.cells[row - 1][column].cell_type;
-                                self.grid.cells[row - 1][column].cell_type = CellType::Oil;
-                                self.grid.cells[row - 1][column].updated = true;
-                            } else {
-                                let mut down_left = false;
-                                let mut down_right = false;
-                                let mut left = false;
-                                let mut right = false;
-                                if column > 0 {
-                                    if self.grid.cells[row - 1][column - 1].cell_type
-                                        != CellType::Sand
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Oil
-                                        && self.grid.cells[row - 1][column - 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        down_left = true;
-                                    }
-                                    if self.grid.cells[row][column - 1].cell_type != CellType::Sand
-                                        && self.grid.cells[row][column - 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row][column - 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row][column - 1].cell_type
-                                            != CellType::Oil
-                                        && self.grid.cells[row][column - 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        left = true;
-                                    }
-                                }
-                                if column < self.grid.columns - 1 {
-                                    if self.grid.cells[row - 1][column + 1].cell_type
-                                        != CellType::Sand
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Oil
-                                        && self.grid.cells[row - 1][column + 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        down_right = true;
-                                    }
-                                    if self.grid.cells[row][column + 1].cell_type != CellType::Sand
-                                        && self.grid.cells[row][column + 1].cell_type
-                                            != CellType::Wall
-                                        && self.grid.cells[row][column + 1].cell_type
-                                            != CellType::Water
-                                        && self.grid.cells[row][column + 1].cell_type
-                                            != CellType::Oil
-                                        && self.grid.cells[row][column + 1].cell_type
-                                            != CellType::Wood
-                                    {
-                                        right = true;
-                                    }
-                                }
-                                if down_left && down_right {
-                                    if rand::random() {
-                                        down_left = false;
-                                    } else {
-                                        down_right = false;
-                                    }
-                                }
-                                if left && right {
-                                    if rand::random() {
-                                        left = false;
-                                    } else {
-                                        right = false;
-                                    }
-                                }
-                                if down_left || down_right {
-                                    if down_left {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row - 1][column - 1].cell_type;
-                                        self.grid.cells[row - 1][column - 1].cell_type =
-                                            CellType::Oil;
-                                        self.grid.cells[row - 1][column - 1].updated = true;
-                                    } else if down_right {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row - 1][column + 1].cell_type;
-                                        self.grid.cells[row - 1][column + 1].cell_type =
-                                            CellType::Oil;
-                                        self.grid.cells[row - 1][column + 1].updated = true;
-                                    }
-                                } else if left || right {
-                                    if right {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row][column + 1].cell_type;
-                                        self.grid.cells[row][column + 1].cell_type = CellType::Oil;
-                                        self.grid.cells[row][column + 1].updated = true;
-                                    } else if left {
-                                        self.grid.cells[row][column].cell_type =
-                                            self.grid.cells[row][column - 1].cell_type;
-                                        self.grid.cells[row][column - 1].cell_type = CellType::Oil;
-                                        self.grid.cells[row][column - 1].updated = true;
-                                    }
-                                }
-                            }
-                        }
-                        // Otherwise if the row index is 0, i.e. it is the bottom row
-                        else {
-                            let mut left = false;
-                            let mut right = false;
-                            if column > 0 {
-                                if self.grid.cells[row][column - 1].cell_type != CellType::Sand
-                                    && self.grid.cells[row][column - 1].cell_type != CellType::Wall
-                                    && self.grid.cells[row][column - 1].cell_type != CellType::Water
-                                    && self.grid.cells[row][column - 1].cell_type != CellType::Oil
-                                    && self.grid.cells[row][column - 1].cell_type != CellType::Wood
-                                {
-                                    left = true;
-                                }
-                            }
-                            if column < self.grid.columns - 1 {
-                                if self.grid.cells[row][column + 1].cell_type != CellType::Sand
-                                    && self.grid.cells[row][column + 1].cell_type != CellType::Wall
-                                    && self.grid.cells[row][column + 1].cell_type != CellType::Water
-                                    && self.grid.cells[row][column + 1].cell_type != CellType::Oil
-                                    && self.grid.cells[row][column + 1].cell_type != CellType::Wood
-                                {
-                                    right = true;
-                                }
-                            }
-                            if left && right {
-                                if rand::random() {
-                                    left = false;
-                                } else {
-                                    right = false;
-                                }
-                            }
-                            if right {
-                                self.grid.cells[row][column].cell_type =
-                                    self.grid.cells[row][column + 1].cell_type;
-                                self.grid.cells[row][column + 1].cell_type = CellType::Oil;
-                                self.grid.cells[row][column + 1].updated = true;
-                            } else if left {
-                                self.grid.cells[row][column].cell_type =
-                                    self.grid.cells[row][column - 1].cell_type;
-                                self.grid.cells[row][column - 1].cell_type = CellType::Oil;
-                                self.grid.cells[row][column - 1].updated = true;
-                            }
-                        }
-                    } else if cell.cell_type == CellType::Steam {
-                        // Randomly condense steam back into water
-                        if rand::random::<f32>() > 0.999 {
-                            self.grid.cells[row][column].cell_type = CellType::Water;
-                        }
-                        // If the row is not the top row
-                        else if row < self.grid.rows - 1 {
-                            if self.grid.cells[row + 1][column].cell_type == CellType::Empty {
-                                self.grid.cells[row][column].cell_type = CellType::Empty;
-                                self.grid.cells[row + 1][column].cell_type = CellType::Steam;
-                                self.grid.cells[row + 1][column].updated = true;
-                            } else {
-                                let mut up_left = false;
-                                let mut up_right = false;
-                                let mut left = false;
-                                let mut right = false;
-                                if column > 0 {
-                                    if self.grid.cells[row + 1][column - 1].cell_type
-                                        == CellType::Empty
-                                    {
-                                        up_left = true;
-                                    }
-                                    if self.grid.cells[row][column - 1].cell_type == CellType::Empty
-                                    {
-                                        left = true;
-                                    }
-                                }
-                                if column < self.grid.columns - 1 {
-                                    if self.grid.cells[row + 1][column + 1].cell_type
-                                        == CellType::Empty
-                                    {
-                                        up_right = true;
-                                    }
-                                    if self.grid.cells[row][column + 1].cell_type == CellType::Empty
-                                    {
-                                        right = true;
-                                    }
-                                }
-                                if up_left && up_right {
-                                    if rand::random() {
-                                        up_left = false;
-                                    } else {
-                                        up_right = false;
-                                    }
-                                }
-                                if left && right {
-                                    if rand::random() {
-                                        left = false;
-                                    } else {
-                                        right = false;
-                                    }
-                                }
-                                if up_left || up_right {
-                                    if up_left {
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row + 1][column - 1].cell_type =
-                                            CellType::Steam;
-                                        self.grid.cells[row + 1][column - 1].updated = true;
-                                    } else if up_right {
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row + 1][column + 1].cell_type =
-                                            CellType::Steam;
-                                        self.grid.cells[row + 1][column + 1].updated = true;
-                                    }
-                                } else {
-                                    if right {
-                                        self.grid.cells[row][column + 1].cell_type =
-                                            CellType::Steam;
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row][column + 1].updated = true;
-                                    } else if left {
-                                        self.grid.cells[row][column - 1].cell_type =
-                                            CellType::Steam;
-                                        self.grid.cells[row][column].cell_type = CellType::Empty;
-                                        self.grid.cells[row][column - 1].updated = true;
-                                    }
-                                }
-                            }
-                        }
-                        // Otherwise if it is the top row
-                        else {
-                            let mut left = false;
-                            let mut right = false;
-                            if column > 0 {
-                                if self.grid.cells[row][column - 1].cell_type == CellType::Empty {
-                                    left = true;
-                                }
-                            }
-                            if column < self.grid.columns - 1 {
-                                if self.grid.cells[row][column + 1].cell_type == CellType::Empty {
-                                    right = true;
-                                }
-                            }
-                            if left && right {
-                                if rand::random() {
-                                    left = false;
-                                } else {
-                                    right = false;
-                                }
-                            }
-                            if right {
-                                self.grid.cells[row][column + 1].cell_type = CellType::Steam;
-                                self.grid.cells[row][column].cell_type = CellType::Empty;
-                                self.grid.cells[row][column + 1].updated = true;
-                            } else if left {
-                                self.grid.cells[row][column - 1].cell_type = CellType::Steam;
-                                self.grid.cells[row][column].cell_type = CellType::Empty;
-                                self.grid.cells[row][column - 1].updated = true;
-                            }
-                        }
-                    }
+        // Update a 'block' of world grid cells (either a subset of the world, or the entire world). The block is swept
+        // one layer at a time from the bottom up (relative to gravity), so that falling cells are updated before the
+        // cells resting on them. Each layer is swept in the opposite direction to the one before, and every layer is
+        // swept in the opposite direction to the previous step, so that cells spreading sideways are not biased towards
+        // one side.
+        let sideways = matches!(self.gravity, Gravity::Left | Gravity::Right);
+        let (layers, length) = if sideways {
+            (columns, rows)
+        } else {
+            (rows, columns)
+        };
+        for layer in 0..layers {
+            let layer = if matches!(self.gravity, Gravity::Up | Gravity::Right) {
+                layers - 1 - layer
+            } else {
+                layer
+            };
+            let forwards = (layer + self.steps as usize).is_multiple_of(2);
+            for index in 0..length {
+                let index = if forwards { index } else { length - 1 - index };
+                if sideways {
+                    self.update_cell(start_row + index, start_column + layer);
+                } else {
+                    self.update_cell(start_row + layer, start_column + index);
+                }
+            }
+        }
+    }
+
+    fn update_cell(&mut self, row: usize, column: usize) {
+        if self.is_updated(row, column) || self.react(row, column) {
+            return;
+        }
+        if let Some(rules) = self.rules.clone() {
+            if rules.covers(self.grid.cells[row][column].cell_type) {
+                self.update_by_rules(&rules, row, column);
+                return;
+            }
+        }
+        match self.grid.cells[row][column].cell_type {
+            CellType::Sand | CellType::Snow => self.update_powder(row, column),
+            CellType::Water | CellType::Oil | CellType::Acid => self.update_liquid(row, column),
+            CellType::Lava => {
+                if self.random::<f32>() < LAVA_FLOW_PROBABILITY {
+                    self.update_liquid(row, column);
+                } else {
+                    // Lava that did not get the chance to move may still be able to
+                    self.wake(row, column);
+                }
+            }
+            CellType::Steam | CellType::Smoke | CellType::ToxicGas => self.update_gas(row, column),
+            CellType::Fire => self.update_fire(row, column),
+            CellType::Seed => self.update_seed(row, column),
+            CellType::Plant => self.update_plant(row, column),
+            CellType::Gunpowder | CellType::Tnt => self.update_explosive(row, column),
+            #[cfg(feature = "scripting")]
+            CellType::Custom1 | CellType::Custom2 | CellType::Custom3 | CellType::Custom4 => {
+                self.update_scripted(row, column)
+            }
+            _ => {}
+        }
+    }
+
+    fn update_powder(&mut self, row: usize, column: usize) {
+        if self.momentum && self.fall(row, column) {
+            return;
+        }
+        if !self.sink(row, column) {
+            self.slide(row, column);
+        }
+    }
+
+    fn update_liquid(&mut self, row: usize, column: usize) {
+        if self.momentum && (self.fall(row, column) || self.drift(row, column)) {
+            return;
+        }
+        if !self.sink(row, column) && !self.slide(row, column) {
+            self.spread(row, column);
+        }
+    }
+
+    fn update_fire(&mut self, row: usize, column: usize) {
+        // Fire burns out, leaving smoke behind
+        if self.random::<f32>() < FIRE_BURN_OUT_PROBABILITY {
+            self.grid.cells[row][column].reset(CellType::Smoke);
+            self.touch(row, column);
+        } else {
+            self.wake(row, column);
+        }
+    }
+
+    fn update_seed(&mut self, row: usize, column: usize) {
+        // Seeds sprout when resting on sand next to water, otherwise they fall like sand
+        let on_sand = self
+            .relative(row, column, 1, 0)
+            .is_some_and(|(below_row, below_column)| {
+                self.grid.cells[below_row][below_column].cell_type == CellType::Sand
+            });
+        if on_sand && self.neighbours(row, column, CellType::Water).1 > 0 {
+            if self.random::<f32>() < SEED_SPROUT_PROBABILITY {
+                self.grid.cells[row][column].reset(CellType::Plant);
+                self.touch(row, column);
+            } else {
+                self.wake(row, column);
+            }
+        } else {
+            self.update_powder(row, column);
+        }
+    }
+
+    fn update_plant(&mut self, row: usize, column: usize) {
+        // Plant cells next to water draw it up and use it to grow the plant from the tip of its stem
+        let (water, water_count) = self.neighbours(row, column, CellType::Water);
+        if water_count == 0 {
+            return;
+        }
+        if self.random::<f32>() >= PLANT_GROWTH_PROBABILITY {
+            self.wake(row, column);
+            return;
+        }
+        // Follow the stem upwards to find the tip to grow from. Where the stem bends there is a chance of stopping and
+        // growing straight up from the bend instead, which splits the plant into branches.
+        let is_plant = |t: CellType| t == CellType::Plant;
+        let mut tip = (row, column);
+        while let Some(above) = self.relative(tip.0, tip.1, -1, 0) {
+            if is_plant(self.grid.cells[above.0][above.1].cell_type) {
+                tip = above;
+            } else if let Some(side) = self.choose_across(tip.0, tip.1, -1, is_plant) {
+                if self.random::<f32>() < PLANT_BRANCH_PROBABILITY {
+                    break;
                 }
-                self.grid.cells[row][column].updated = true;
+                tip = side;
+            } else {
+                break;
+            }
+        }
+        // Grow straight up, or occasionally branch diagonally up to either side. Plants can grow through water, using
+        // up the water they grow into rather than the water next to the plant cell.
+        let can_grow = |t: CellType| t == CellType::Empty || t == CellType::Water;
+        let above = self
+            .relative(tip.0, tip.1, -1, 0)
+            .filter(|&(above_row, above_column)| {
+                can_grow(self.grid.cells[above_row][above_column].cell_type)
+            });
+        let (growth_row, growth_column) = match self.choose_across(tip.0, tip.1, -1, can_grow) {
+            Some(side) if above.is_none() || self.random::<f32>() < PLANT_BRANCH_PROBABILITY => {
+                side
             }
+            _ => match above {
+                Some(above) => above,
+                None => return,
+            },
+        };
+        if self.grid.cells[growth_row][growth_column].cell_type != CellType::Water {
+            let (water_row, water_column) = water[self.random_range(0..water_count)];
+            self.grid.cells[water_row][water_column].reset(CellType::Empty);
+            self.touch(water_row, water_column);
         }
+        self.grid.cells[growth_row][growth_column].reset(CellType::Plant);
+        self.touch(growth_row, growth_column);
+    }
+
+    fn update_explosive(&mut self, row: usize, column: usize) {
+        // Explosives detonate when they touch fire, otherwise gunpowder falls like sand
+        let cell_type = self.grid.cells[row][column].cell_type;
+        if let Some(radius) = cell_type.blast_radius() {
+            if self.neighbours(row, column, CellType::Fire).1 > 0 {
+                self.events.push(Event::Explosion {
+                    row,
+                    column,
+                    radius,
+                });
+                self.touch(row, column);
+                return;
+            }
+        }
+        if cell_type == CellType::Gunpowder {
+            self.update_powder(row, column);
+        }
+    }
+
+    // Moves a cell into the cell below it if it can displace it. A displaced liquid is pushed up and to the side where
+    // there is room, otherwise the two cells swap.
+    fn sink(&mut self, row: usize, column: usize) -> bool {
+        let cell_type = self.grid.cells[row][column].cell_type;
+        let (below_row, below_column) = match self.relative(row, column, 1, 0) {
+            Some(below) => below,
+            None => return false,
+        };
+        let below = self.grid.cells[below_row][below_column].cell_type;
+        if !cell_type.can_displace(below) {
+            return false;
+        }
+        if below.is_liquid() {
+            if let Some((side_row, side_column)) =
+                self.choose_across(row, column, 0, |t| t == CellType::Empty)
+            {
+                // Move the cells round, the empty cell taking the sinking cell's place (at rest)
+                let side = self.grid.cells[side_row][side_column];
+                self.grid.cells[side_row][side_column] = self.grid.cells[below_row][below_column];
+                self.grid.cells[below_row][below_column] = self.grid.cells[row][column];
+                self.grid.cells[row][column] = side;
+                self.grid.cells[row][column].reset(CellType::Empty);
+                self.touch(row, column);
+                self.touch(side_row, side_column);
+                self.touch(below_row, below_column);
+                return true;
+            }
+        }
+        self.swap(row, column, below_row, below_column);
+        true
+    }
+
+    // Moves a cell diagonally down to either side if it can displace the cell there. Snow piles more steeply than sand,
+    // so it only slides down a side where it would drop at least two cells.
+    fn slide(&mut self, row: usize, column: usize) -> bool {
+        let cell_type = self.grid.cells[row][column].cell_type;
+        let can_displace = |world: &World, (side_row, side_column): (usize, usize)| {
+            cell_type.can_displace(world.grid.cells[side_row][side_column].cell_type)
+        };
+        let can_slide = |world: &World, side: (usize, usize)| {
+            can_displace(world, side)
+                && (cell_type != CellType::Snow
+                    || world
+                        .relative(side.0, side.1, 1, 0)
+                        .is_some_and(|below| can_displace(world, below)))
+        };
+        match self.choose_position(row, column, 1, can_slide) {
+            Some((side_row, side_column)) => {
+                self.swap(row, column, side_row, side_column);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Moves a cell sideways to either side if it can displace the cell there
+    fn spread(&mut self, row: usize, column: usize) -> bool {
+        let cell_type = self.grid.cells[row][column].cell_type;
+        match self.choose_across(row, column, 0, |t| cell_type.can_displace(t)) {
+            Some((side_row, side_column)) => {
+                self.swap(row, column, side_row, side_column);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn swap(&mut self, row: usize, column: usize, other_row: usize, other_column: usize) {
+        let cell = self.grid.cells[row][column];
+        self.grid.cells[row][column] = self.grid.cells[other_row][other_column];
+        self.grid.cells[other_row][other_column] = cell;
+        self.touch(row, column);
+        self.touch(other_row, other_column);
+    }
+
+    // Applies the first matching reaction (if any) between a cell and its neighbours, returning whether one took place
+    fn react(&mut self, row: usize, column: usize) -> bool {
+        let cell_type = self.grid.cells[row][column].cell_type;
+        for reaction_number in 0..self.reactions_by_type[cell_type as usize].len() {
+            let reaction =
+                self.reactions[self.reactions_by_type[cell_type as usize][reaction_number]];
+            let (neighbours, count) = self.neighbours(row, column, reaction.neighbour);
+            if count == 0 || count < reaction.contacts {
+                continue;
+            }
+            if self.random::<f32>() < reaction.probability {
+                let (neighbour_row, neighbour_column) = neighbours[self.random_range(0..count)];
+                self.grid.cells[row][column].reset(reaction.cell_becomes);
+                self.grid.cells[neighbour_row][neighbour_column].reset(reaction.neighbour_becomes);
+                self.touch(row, column);
+                self.touch(neighbour_row, neighbour_column);
+                return true;
+            }
+            // The reaction may still take place in a later step
+            self.wake(row, column);
+        }
+        false
+    }
+
+    // Returns the positions of the (not yet updated) neighbouring cells of the given type that can take part in a
+    // reaction, as an array of which only the given number are filled in (to avoid allocating for every cell)
+    fn neighbours(
+        &self,
+        row: usize,
+        column: usize,
+        cell_type: CellType,
+    ) -> ([(usize, usize); 8], usize) {
+        let mut neighbours = [(0, 0); 8];
+        let mut count = 0;
+        if self.immune.contains(&cell_type) {
+            return (neighbours, count);
+        }
+        for neighbour_row in row.saturating_sub(1)..=(row + 1).min(self.grid.rows - 1) {
+            for neighbour_column in
+                column.saturating_sub(1)..=(column + 1).min(self.grid.columns - 1)
+            {
+                if (neighbour_row, neighbour_column) != (row, column)
+                    && self.grid.cells[neighbour_row][neighbour_column].cell_type == cell_type
+                    && !self.is_updated(neighbour_row, neighbour_column)
+                {
+                    neighbours[count] = (neighbour_row, neighbour_column);
+                    count += 1;
+                }
+            }
+        }
+        (neighbours, count)
+    }
+
+    fn random<T>(&mut self) -> T
+    where
+        Standard: Distribution<T>,
+    {
+        self.rng.gen()
+    }
+
+    fn random_range<T, R>(&mut self, range: R) -> T
+    where
+        T: SampleUniform,
+        R: SampleRange<T>,
+    {
+        self.rng.gen_range(range)
+    }
+
+    // Returns whether the cell changed in the last step performed (for visualising what the engine is doing)
+    pub fn changed_in_last_step(&self, row: usize, column: usize) -> bool {
+        self.steps > 0 && self.is_updated(row, column)
+    }
+
+    // Returns whether the cell has already changed in the current step
+    fn is_updated(&self, row: usize, column: usize) -> bool {
+        self.grid.cells[row][column].stamp == self.stamp
     }
 
     pub fn clear(&mut self) {
         for row in 0..self.grid.rows {
             for column in 0..self.grid.columns {
-                self.grid.cells[row][column].cell_type = CellType::Empty;
+                self.grid.cells[row][column].reset(CellType::Empty);
             }
         }
+        // Drop explosions chained from the last step too, so that they do not go off in the cleared world
+        self.events.clear();
+        self.wake_all();
     }
 }
+
+// Returns a random number generator seeded by the operating system, or with a fixed seed on wasm32, where there is no
+// operating system to ask (the browser frontend reseeds each world from the clock instead)
+#[cfg(not(target_arch = "wasm32"))]
+fn entropy_rng() -> StdRng {
+    StdRng::from_entropy()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn entropy_rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+// Returns the paths of the files in the directory with the given extension (e.g. "rules"), in order of their names, for
+// loading materials
+fn files_with_extension(directory: &Path, extension: &str) -> Result<Vec<PathBuf>, String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(directory)
+        .map_err(|error| format!("{}: {}", directory.display(), error))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|other| other == extension))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
@@ -0,0 +1,104 @@
+// This module defines the movement of cells that have momentum (when enabled for the world).
+//
+// Falling powders and liquids build up speed under gravity and can travel several cells per step, stopping short of the
+// first cell in their path that is not empty. When a falling cell lands, its speed is lost, except for fast falling
+// liquids which splash, turning their speed into a sideways velocity that carries them across the surface they land on.
+
+use super::{Cell, CellType, World};
+
+const GRAVITY: f32 = 0.25; // Increase in falling speed per step at a gravity strength of 1, in cells per step
+const MAX_VELOCITY: f32 = 8.0; // In cells per step
+const SPLASH_VELOCITY: f32 = 2.0; // Minimum landing speed for a liquid to splash, in cells per step
+const SPLASH_FACTOR: f32 = 0.75; // Fraction of the landing speed that is turned into sideways velocity
+const FRICTION: f32 = 0.8; // Fraction of the sideways velocity that is kept after each step
+const VELOCITY_SCALE: f32 = 16.0; // Velocities are stored in sixteenths of a cell per step
+
+impl Cell {
+    fn velocity_x(&self) -> f32 {
+        self.velocity_x as f32 / VELOCITY_SCALE
+    }
+
+    fn velocity_y(&self) -> f32 {
+        self.velocity_y as f32 / VELOCITY_SCALE
+    }
+
+    fn set_velocity_x(&mut self, velocity: f32) {
+        self.velocity_x = (velocity * VELOCITY_SCALE).round().clamp(-128.0, 127.0) as i8;
+    }
+
+    fn set_velocity_y(&mut self, velocity: f32) {
+        self.velocity_y = (velocity * VELOCITY_SCALE).round().clamp(-128.0, 127.0) as i8;
+    }
+}
+
+impl World {
+    // Moves a cell straight down through empty cells, accelerating under gravity. If the cell below is not empty the
+    // cell lands instead, and false is returned.
+    pub(super) fn fall(&mut self, row: usize, column: usize) -> bool {
+        let mut target = match self.relative(row, column, 1, 0) {
+            Some((below_row, below_column))
+                if self.grid.cells[below_row][below_column].cell_type == CellType::Empty =>
+            {
+                (below_row, below_column)
+            }
+            _ => {
+                self.land(row, column);
+                return false;
+            }
+        };
+        let velocity = (self.grid.cells[row][column].velocity_y()
+            - GRAVITY * self.gravity_strength)
+            .max(-MAX_VELOCITY);
+        let distance = (-velocity).max(1.0) as usize;
+        // Cast a ray down the path of the cell, stopping at the last empty cell
+        for _ in 1..distance {
+            match self.relative(target.0, target.1, 1, 0) {
+                Some(next) if self.grid.cells[next.0][next.1].cell_type == CellType::Empty => {
+                    target = next;
+                }
+                _ => break,
+            }
+        }
+        self.grid.cells[row][column].set_velocity_y(velocity);
+        self.swap(row, column, target.0, target.1);
+        true
+    }
+
+    // Moves a cell across through empty cells according to its sideways velocity, which is slowed by friction
+    pub(super) fn drift(&mut self, row: usize, column: usize) -> bool {
+        let velocity = self.grid.cells[row][column].velocity_x();
+        if velocity.abs() < 1.0 {
+            self.grid.cells[row][column].velocity_x = 0;
+            return false;
+        }
+        let direction = if velocity < 0.0 { -1 } else { 1 };
+        // Cast a ray along the path of the cell, stopping at the last empty cell
+        let mut target = (row, column);
+        for _ in 0..velocity.abs() as usize {
+            match self.relative(target.0, target.1, 0, direction) {
+                Some(next) if self.grid.cells[next.0][next.1].cell_type == CellType::Empty => {
+                    target = next;
+                }
+                _ => break,
+            }
+        }
+        if target == (row, column) {
+            self.grid.cells[row][column].velocity_x = 0;
+            return false;
+        }
+        self.grid.cells[row][column].set_velocity_x(velocity * FRICTION);
+        self.swap(row, column, target.0, target.1);
+        true
+    }
+
+    fn land(&mut self, row: usize, column: usize) {
+        let cell = self.grid.cells[row][column];
+        if cell.cell_type.is_liquid() && -cell.velocity_y() >= SPLASH_VELOCITY {
+            let splash = -cell.velocity_y() * SPLASH_FACTOR;
+            let splash = if self.random() { splash } else { -splash };
+            self.grid.cells[row][column].set_velocity_x(splash);
+            self.wake(row, column);
+        }
+        self.grid.cells[row][column].velocity_y = 0;
+    }
+}
@@ -0,0 +1,201 @@
+// This module defines the reactions that take place between neighbouring cells.
+//
+// A reaction is of the form "A next to B becomes C + D with probability p" where:
+//     - A is the reacting cell and C is the type it becomes;
+//     - B is a neighbouring cell (any of the 8 surrounding cells) and D is the type it becomes;
+//     - The reaction only takes place if A has at least the given number of B neighbours;
+//     - The probability is evaluated once per step for each reacting cell.
+//
+// Reactions are evaluated in table order and the first reaction to take place wins. Cells of an immune type are never
+// changed by a reaction with a neighbouring cell.
+
+use super::CellType;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Reaction {
+    pub cell: CellType,
+    pub neighbour: CellType,
+    pub contacts: usize, // Minimum number of neighbouring cells of the neighbour type
+    pub cell_becomes: CellType,
+    pub neighbour_becomes: CellType,
+    pub probability: f32,
+}
+
+impl Reaction {
+    pub const fn new(
+        cell: CellType,
+        neighbour: CellType,
+        cell_becomes: CellType,
+        neighbour_becomes: CellType,
+        probability: f32,
+    ) -> Self {
+        Reaction {
+            cell,
+            neighbour,
+            contacts: 1,
+            cell_becomes,
+            neighbour_becomes,
+            probability,
+        }
+    }
+
+    pub const fn with_contacts(mut self, contacts: usize) -> Self {
+        self.contacts = contacts;
+        self
+    }
+}
+
+pub const IMMUNE: &[CellType] = &[CellType::Wall, CellType::Glass];
+
+pub const REACTIONS: &[Reaction] = &[
+    // Lava that is quenched fast (mostly surrounded by water) sets into obsidian
+    Reaction::new(
+        CellType::Lava,
+        CellType::Water,
+        CellType::Obsidian,
+        CellType::Steam,
+        0.5,
+    )
+    .with_contacts(3),
+    // Otherwise lava slowly cools into stone, boiling the water it touches
+    Reaction::new(
+        CellType::Lava,
+        CellType::Water,
+        CellType::Stone,
+        CellType::Steam,
+        0.05,
+    ),
+    // Lava sets flammable materials on fire
+    Reaction::new(
+        CellType::Lava,
+        CellType::Oil,
+        CellType::Lava,
+        CellType::Fire,
+        0.1,
+    ),
+    Reaction::new(
+        CellType::Lava,
+        CellType::Wood,
+        CellType::Lava,
+        CellType::Fire,
+        0.02,
+    ),
+    Reaction::new(
+        CellType::Lava,
+        CellType::Plant,
+        CellType::Lava,
+        CellType::Fire,
+        0.02,
+    ),
+    Reaction::new(
+        CellType::Lava,
+        CellType::Seed,
+        CellType::Lava,
+        CellType::Fire,
+        0.1,
+    ),
+    // Water puts out fire
+    Reaction::new(
+        CellType::Fire,
+        CellType::Water,
+        CellType::Empty,
+        CellType::Steam,
+        0.5,
+    ),
+    // Fire spreads to flammable materials
+    Reaction::new(
+        CellType::Fire,
+        CellType::Oil,
+        CellType::Fire,
+        CellType::Fire,
+        0.2,
+    ),
+    Reaction::new(
+        CellType::Fire,
+        CellType::Wood,
+        CellType::Fire,
+        CellType::Fire,
+        0.05,
+    ),
+    Reaction::new(
+        CellType::Fire,
+        CellType::Plant,
+        CellType::Fire,
+        CellType::Fire,
+        0.05,
+    ),
+    Reaction::new(
+        CellType::Fire,
+        CellType::Seed,
+        CellType::Fire,
+        CellType::Fire,
+        0.1,
+    ),
+    // Ice and snow melt near heat
+    Reaction::new(
+        CellType::Ice,
+        CellType::Lava,
+        CellType::Water,
+        CellType::Lava,
+        0.1,
+    ),
+    Reaction::new(
+        CellType::Ice,
+        CellType::Fire,
+        CellType::Water,
+        CellType::Fire,
+        0.1,
+    ),
+    Reaction::new(
+        CellType::Snow,
+        CellType::Lava,
+        CellType::Water,
+        CellType::Lava,
+        0.2,
+    ),
+    Reaction::new(
+        CellType::Snow,
+        CellType::Fire,
+        CellType::Water,
+        CellType::Fire,
+        0.2,
+    ),
+    // Ice slowly freezes the water around it
+    Reaction::new(
+        CellType::Ice,
+        CellType::Water,
+        CellType::Ice,
+        CellType::Ice,
+        0.002,
+    ),
+    // Steam settles on ice as snow
+    Reaction::new(
+        CellType::Steam,
+        CellType::Ice,
+        CellType::Snow,
+        CellType::Ice,
+        0.05,
+    ),
+    // Acid dissolves materials, consuming itself in the process and giving off toxic gas
+    Reaction::new(
+        CellType::Acid,
+        CellType::Sand,
+        CellType::ToxicGas,
+        CellType::Empty,
+        0.05,
+    ),
+    Reaction::new(
+        CellType::Acid,
+        CellType::Wood,
+        CellType::ToxicGas,
+        CellType::Empty,
+        0.05,
+    ),
+    Reaction::new(
+        CellType::Acid,
+        CellType::Stone,
+        CellType::ToxicGas,
+        CellType::Empty,
+        0.02,
+    ),
+];
@@ -0,0 +1,406 @@
+// This module defines a small language for describing how materials behave as pattern rewriting rules, parsed at load
+// time (see src/engine/tests/classic.rules, which describes sand, water, oil and steam exactly as the engine does).
+//
+// Each line is a rule of the form:
+//
+//     <materials> : <conditions> => <actions> [mirror] [chance <probability>]
+//
+// e.g. 'sand : down is displaceable => swap down', where:
+//     - <materials> is one or more cell type identifiers (see CellType::identifier) separated by '|';
+//     - <conditions> is zero or more conditions separated by 'and', each of the form '<position> is <classes>', which
+//       holds if the cell at the position is of any of the classes (separated by '|'). A class is a cell type
+//       identifier, or one of:
+//           displaceable    a cell that the cell being updated is dense enough to move into
+//           liquid          a liquid (water, oil, lava or acid)
+//           gas             a gas (see the gases module)
+//           heavier_gas     a gas that is denser than the cell being updated (which must be a gas)
+//           lighter_gas     a gas that is less dense than the cell being updated (which must be a gas)
+//           other_gas       a gas of a different type to the cell being updated
+//     - <actions> is one or more actions separated by ',', each either '<position> <- <source>', which puts the cell
+//       that was at the source position (or a new cell, if the source is a cell type identifier) at the position, or
+//       'swap <position>', which is short for 'here <- <position>, <position> <- here';
+//     - positions are one of here, up, down, left, right, up_left, up_right, down_left and down_right, relative to the
+//       direction of gravity, and a rule only matches if all of the positions it uses are inside the world;
+//     - 'mirror' also tries the rule with left and right exchanged, choosing one way at random if both match (with
+//       gravity off, the rule applies along a randomly chosen axis, and rules that use up or down never match);
+//     - 'chance' only tries the rule with the given probability, drawing a random number every time the rule is
+//       reached.
+//
+// Blank lines and everything after a '#' are ignored. A material's rules are tried in order, and the first one that
+// matches is applied. Materials with rules behave only according to those rules (although they still take part in
+// reactions), so they do not have momentum. The chunk of a material with any rules that have a chance is kept awake, as
+// such a rule may apply in a later step when it did not in this one.
+
+use super::{files_with_extension, Cell, CellType, Gravity, World};
+use std::fs;
+use std::path::Path;
+
+pub struct Rules {
+    materials: Vec<Vec<Rule>>, // Rules of each cell type, in order of their codes
+}
+
+#[derive(Clone)]
+struct Rule {
+    mirror: bool,
+    chance: Option<f32>,
+    conditions: Vec<(Position, Vec<Class>)>,
+    actions: Vec<(Position, Source)>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct Position {
+    down: isize,
+    across: isize,
+}
+
+#[derive(Copy, Clone)]
+enum Class {
+    Type(CellType),
+    Displaceable,
+    Liquid,
+    Gas,
+    HeavierGas,
+    LighterGas,
+    OtherGas,
+}
+
+#[derive(Copy, Clone)]
+enum Source {
+    Position(Position),
+    Type(CellType),
+}
+
+const HERE: Position = Position { down: 0, across: 0 };
+
+impl Rules {
+    // Where the frontends look for rules, relative to the working directory (alongside the scripts)
+    pub const DIRECTORY: &'static str = "materials";
+
+    pub fn parse(text: &str) -> Result<Rules, String> {
+        let mut rules = Rules {
+            materials: CellType::ALL.iter().map(|_| Vec::new()).collect(),
+        };
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (materials, rule) =
+                parse_rule(line).map_err(|error| format!("Line {}: {}", line_number + 1, error))?;
+            for material in materials {
+                rules.materials[material as usize].push(rule.clone());
+            }
+        }
+        Ok(rules)
+    }
+
+    // Parses the rules in every file (*.rules) in the directory, in order of their file names
+    pub fn load_directory(directory: &Path) -> Result<Rules, String> {
+        let paths = files_with_extension(directory, "rules")?;
+        let mut text = String::new();
+        for path in paths {
+            // Parse each file on its own first, so that errors refer to the right file and line
+            let file = fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|file| Rules::parse(&file).map(|_| file))
+                .map_err(|error| format!("{}: {}", path.display(), error))?;
+            text.push_str(&file);
+            text.push('\n');
+        }
+        Rules::parse(&text)
+    }
+
+    // Returns whether the cell type behaves according to rules rather than the engine's built in behaviour
+    pub fn covers(&self, cell_type: CellType) -> bool {
+        !self.materials[cell_type as usize].is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.iter().all(|rules| rules.is_empty())
+    }
+}
+
+impl Rule {
+    // Returns every position the rule refers to
+    fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        let conditions = self.conditions.iter().map(|&(position, _)| position);
+        let targets = self.actions.iter().map(|&(position, _)| position);
+        let sources = self.actions.iter().filter_map(|&(_, source)| match source {
+            Source::Position(position) => Some(position),
+            Source::Type(_) => None,
+        });
+        conditions.chain(targets).chain(sources)
+    }
+}
+
+fn parse_rule(line: &str) -> Result<(Vec<CellType>, Rule), String> {
+    let (materials, rest) = line
+        .split_once(':')
+        .ok_or("Expected ':' after the materials")?;
+    let (conditions, actions) = rest
+        .split_once("=>")
+        .ok_or("Expected '=>' before the actions")?;
+    let materials = materials
+        .split('|')
+        .map(|material| parse_cell_type(material.trim()))
+        .collect::<Result<Vec<CellType>, String>>()?;
+    let mut rule = Rule {
+        mirror: false,
+        chance: None,
+        conditions: Vec::new(),
+        actions: Vec::new(),
+    };
+    // Conditions
+    let conditions = conditions.trim();
+    if !conditions.is_empty() {
+        for condition in conditions.split(" and ") {
+            let words: Vec<&str> = condition.split_whitespace().collect();
+            match words.as_slice() {
+                [position, "is", classes] => rule.conditions.push((
+                    parse_position(position)?,
+                    classes
+                        .split('|')
+                        .map(parse_class)
+                        .collect::<Result<Vec<Class>, String>>()?,
+                )),
+                _ => {
+                    return Err(format!(
+                        "Expected a condition of the form '<position> is <classes>', not '{}'",
+                        condition.trim()
+                    ))
+                }
+            }
+        }
+    }
+    // Actions, followed by options
+    let actions = actions.replace(',', " , ");
+    let mut words = actions.split_whitespace().peekable();
+    loop {
+        match (words.next(), words.next(), words.peek()) {
+            (Some("swap"), Some(position), _) => {
+                let position = parse_position(position)?;
+                rule.actions.push((HERE, Source::Position(position)));
+                rule.actions.push((position, Source::Position(HERE)));
+            }
+            (Some(position), Some("<-"), Some(source)) => {
+                let source = match parse_position(source) {
+                    Ok(source) => Source::Position(source),
+                    Err(_) => Source::Type(parse_cell_type(source)?),
+                };
+                rule.actions.push((parse_position(position)?, source));
+                words.next();
+            }
+            _ => {
+                return Err(
+                    "Expected an action of the form '<position> <- <source>' or 'swap <position>'"
+                        .to_owned(),
+                )
+            }
+        }
+        if words.peek() != Some(&",") {
+            break;
+        }
+        words.next();
+    }
+    while let Some(word) = words.next() {
+        match word {
+            "mirror" => rule.mirror = true,
+            "chance" => {
+                let chance = words
+                    .next()
+                    .ok_or("Expected a probability after 'chance'")?;
+                rule.chance = Some(
+                    chance
+                        .parse()
+                        .map_err(|_| format!("Expected a probability, not '{}'", chance))?,
+                );
+            }
+            other => return Err(format!("Unknown option '{}'", other)),
+        }
+    }
+    for (index, (position, _)) in rule.actions.iter().enumerate() {
+        if rule.actions[..index]
+            .iter()
+            .any(|(other, _)| other == position)
+        {
+            return Err("A position is changed by more than one action".to_owned());
+        }
+    }
+    Ok((materials, rule))
+}
+
+fn parse_cell_type(word: &str) -> Result<CellType, String> {
+    CellType::from_identifier(word).ok_or(format!("Unknown cell type '{}'", word))
+}
+
+fn parse_position(word: &str) -> Result<Position, String> {
+    let (down, across) = match word {
+        "here" => (0, 0),
+        "up" => (-1, 0),
+        "down" => (1, 0),
+        "left" => (0, -1),
+        "right" => (0, 1),
+        "up_left" => (-1, -1),
+        "up_right" => (-1, 1),
+        "down_left" => (1, -1),
+        "down_right" => (1, 1),
+        other => return Err(format!("Unknown position '{}'", other)),
+    };
+    Ok(Position { down, across })
+}
+
+fn parse_class(word: &str) -> Result<Class, String> {
+    Ok(match word {
+        "displaceable" => Class::Displaceable,
+        "liquid" => Class::Liquid,
+        "gas" => Class::Gas,
+        "heavier_gas" => Class::HeavierGas,
+        "lighter_gas" => Class::LighterGas,
+        "other_gas" => Class::OtherGas,
+        other => Class::Type(
+            CellType::from_identifier(other).ok_or(format!("Unknown class '{}'", other))?,
+        ),
+    })
+}
+
+impl Class {
+    // Returns whether a cell of the given type is of the class, as seen by the cell being updated
+    fn contains(self, cell_type: CellType, other: CellType) -> bool {
+        let gas_densities = || cell_type.gas().zip(other.gas());
+        match self {
+            Class::Type(class_type) => other == class_type,
+            Class::Displaceable => cell_type.can_displace(other),
+            Class::Liquid => other.is_liquid(),
+            Class::Gas => other.gas().is_some(),
+            Class::HeavierGas => {
+                gas_densities().is_some_and(|(gas, other)| other.density > gas.density)
+            }
+            Class::LighterGas => {
+                gas_densities().is_some_and(|(gas, other)| other.density < gas.density)
+            }
+            Class::OtherGas => other != cell_type && other.gas().is_some(),
+        }
+    }
+}
+
+impl World {
+    // Updates a cell whose material is covered by the rules
+    pub(super) fn update_by_rules(&mut self, rules: &Rules, row: usize, column: usize) {
+        let cell_type = self.grid.cells[row][column].cell_type;
+        let material = &rules.materials[cell_type as usize];
+        if material.iter().any(|rule| rule.chance.is_some()) {
+            self.wake(row, column);
+        }
+        for rule in material {
+            if let Some(chance) = rule.chance {
+                if self.random::<f32>() >= chance {
+                    continue;
+                }
+            }
+            if let Some(positions) = self.match_rule(rule, row, column) {
+                self.apply_rule(rule, &positions);
+                return;
+            }
+        }
+    }
+
+    // Returns the positions in the world of the cells the rule's actions refer to (in the same order as the actions,
+    // followed by their sources), if the rule matches in the chosen orientation
+    fn match_rule(
+        &mut self,
+        rule: &Rule,
+        row: usize,
+        column: usize,
+    ) -> Option<Vec<(usize, usize)>> {
+        if !rule.mirror {
+            return self.match_orientation(rule, row, column, false, false);
+        }
+        // As in choose_position, with gravity off the rule applies along a random axis, and rules that use up or down
+        // never match
+        let rows = if self.gravity == Gravity::Off {
+            if rule.positions().any(|position| position.down != 0) {
+                return None;
+            }
+            !self.random::<bool>()
+        } else {
+            false
+        };
+        let as_written = self.match_orientation(rule, row, column, false, rows);
+        let mirrored = self.match_orientation(rule, row, column, true, rows);
+        match (as_written, mirrored) {
+            (Some(as_written), Some(mirrored)) => {
+                if self.random() {
+                    Some(mirrored)
+                } else {
+                    Some(as_written)
+                }
+            }
+            (Some(as_written), None) => Some(as_written),
+            (None, mirrored) => mirrored,
+        }
+    }
+
+    fn match_orientation(
+        &self,
+        rule: &Rule,
+        row: usize,
+        column: usize,
+        mirrored: bool,
+        rows: bool, // Whether across is along the rows, for rules applied along a random axis with gravity off
+    ) -> Option<Vec<(usize, usize)>> {
+        let locate = |position: Position| {
+            let across = if mirrored {
+                -position.across
+            } else {
+                position.across
+            };
+            if rows {
+                self.position(row as isize + across, column as isize)
+            } else {
+                self.relative(row, column, position.down, across)
+            }
+        };
+        let cell_type = self.grid.cells[row][column].cell_type;
+        for &(position, ref classes) in &rule.conditions {
+            let (other_row, other_column) = locate(position)?;
+            let other = self.grid.cells[other_row][other_column].cell_type;
+            if !classes.iter().any(|class| class.contains(cell_type, other)) {
+                return None;
+            }
+        }
+        let mut positions = Vec::new();
+        for &(position, _) in &rule.actions {
+            positions.push(locate(position)?);
+        }
+        for &(_, source) in &rule.actions {
+            if let Source::Position(source) = source {
+                positions.push(locate(source)?);
+            }
+        }
+        Some(positions)
+    }
+
+    fn apply_rule(&mut self, rule: &Rule, positions: &[(usize, usize)]) {
+        // Work out every new cell before changing any, as the sources refer to the cells as they were
+        let (targets, mut sources) = positions.split_at(rule.actions.len());
+        let mut cells: Vec<Cell> = Vec::new();
+        for (&(_, source), &(target_row, target_column)) in rule.actions.iter().zip(targets) {
+            cells.push(match source {
+                Source::Position(_) => {
+                    let (source_row, source_column) = sources[0];
+                    sources = &sources[1..];
+                    self.grid.cells[source_row][source_column]
+                }
+                Source::Type(cell_type) => {
+                    let mut cell = self.grid.cells[target_row][target_column];
+                    cell.reset(cell_type);
+                    cell
+                }
+            });
+        }
+        for (&(target_row, target_column), cell) in targets.iter().zip(cells) {
+            self.grid.cells[target_row][target_column] = cell;
+            self.touch(target_row, target_column);
+        }
+    }
+}
@@ -0,0 +1,301 @@
+// This module lets the behaviour of the custom materials (CellType::Custom1 to Custom4) be defined by scripts written
+// in Rhai (https://rhai.rs), loaded at startup, so that materials can be added without recompiling.
+//
+// A script must define an update function, which is called in every step for each cell of its material:
+//
+//     fn update(cells, material) { ... }
+//
+// cells is the cell's 3 x 3 neighbourhood, as three rows of three cell type identifiers (see CellType::identifier),
+// laid out relative to the direction of gravity: cells[0] is the row above the cell, cells[2] the row below it, and
+// cells[1][1] is the cell itself, with the cells to either side of it at cells[1][0] and cells[1][2]. Cells outside the
+// world (and, with gravity off, the rows above and below) read as "wall". material is the identifier of the script's
+// own material, which depends on the order the scripts were loaded in (e.g. "custom_1"). Scripts can call random() as
+// often as they like for a number between 0 and 1, drawn from the world's random number generator, so that a seeded
+// world runs the same way every time.
+//
+// update returns nothing, or what the cell does as a map of any of the following (which are applied in this order):
+//     set: [down, across, type]   Turn a neighbouring cell into the given type, if it is empty or a powder, liquid or
+//                                 gas that is not immune and has not already changed in this step. The offsets are
+//                                 each -1, 0 or 1, as in cells (e.g. [1, 0] is the cell below).
+//     become: type                Turn the cell itself into the given type
+//     swap: [down, across]        Swap places with a neighbouring cell, if it is empty or a powder, liquid or gas that
+//                                 has not already changed in this step
+// e.g. #{ swap: [1, 0] } to fall straight down, or #{ become: "stone" }.
+//
+// A script can also define a colour function, returning the material's colour as [red, green, blue] (each between 0 and
+// 1). Cells whose script fails or returns something else are left unchanged, and the number of operations a script can
+// perform in each call is limited, so that a faulty script cannot hang the simulation. As scripts may act at random,
+// the chunks containing scripted cells never sleep.
+
+use super::{files_with_extension, CellType, World};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const MAX_OPERATIONS: u64 = 10_000; // Per call of a script's function
+
+pub struct Scripts {
+    engine: Engine,
+    materials: Vec<(CellType, Script)>,
+    identifiers: Vec<Dynamic>, // Identifier of each cell type, in order of their codes, as passed to scripts
+    rng: Arc<Mutex<StdRng>>, // Drawn from by random(), holding the world's generator while a script is called
+}
+
+struct Script {
+    name: String,
+    ast: AST,
+    colour: Option<[f32; 3]>,
+}
+
+enum Action {
+    Set(isize, isize, CellType),
+    Become(CellType),
+    Swap(isize, isize),
+}
+
+impl Scripts {
+    // Where the frontends look for scripts, relative to the working directory
+    pub const DIRECTORY: &'static str = "materials";
+
+    pub fn new() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // Use the same limits on nesting in debug builds as in release builds (where they are more generous)
+        engine.set_max_expr_depths(64, 32);
+        let rng = Arc::new(Mutex::new(StdRng::seed_from_u64(0)));
+        let script_rng = rng.clone();
+        engine.register_fn("random", move || script_rng.lock().unwrap().gen::<f64>());
+        Scripts {
+            engine,
+            rng,
+            materials: Vec::new(),
+            identifiers: CellType::ALL
+                .iter()
+                .map(|cell_type| cell_type.identifier().into())
+                .collect(),
+        }
+    }
+
+    // Loads the scripts (*.rhai files) in the directory as the custom materials, in order of their file names, so that
+    // the first becomes Custom1 and so on. Each material is named after its file.
+    pub fn load_directory(directory: &Path) -> Result<Scripts, String> {
+        let paths = files_with_extension(directory, "rhai")?;
+        if paths.len() > CellType::CUSTOM.len() {
+            return Err(format!(
+                "{}: there are {} scripts, but only {} custom materials",
+                directory.display(),
+                paths.len(),
+                CellType::CUSTOM.len()
+            ));
+        }
+        let mut scripts = Scripts::new();
+        for (path, cell_type) in paths.iter().zip(CellType::CUSTOM) {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            fs::read_to_string(path)
+                .map_err(|error| error.to_string())
+                .and_then(|source| scripts.load(cell_type, &name, &source))
+                .map_err(|error| format!("{}: {}", path.display(), error))?;
+        }
+        Ok(scripts)
+    }
+
+    // Loads the script for a custom material, replacing any script it already has
+    pub fn load(&mut self, cell_type: CellType, name: &str, source: &str) -> Result<(), String> {
+        if !CellType::CUSTOM.contains(&cell_type) {
+            return Err(format!("{} is not a custom material", cell_type.name()));
+        }
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|error| error.to_string())?;
+        let has_function = |name: &str, parameters: usize| {
+            ast.iter_functions()
+                .any(|function| function.name == name && function.params.len() == parameters)
+        };
+        if !has_function("update", 2) {
+            return Err("The script has no update(cells, material) function".to_owned());
+        }
+        let colour = if has_function("colour", 0) {
+            let colour: Array = self.call(&ast, "colour", ())?;
+            match colour
+                .iter()
+                .map(|component| component.as_float().ok().map(|component| component as f32))
+                .collect::<Option<Vec<f32>>>()
+                .as_deref()
+            {
+                Some(&[red, green, blue]) => Some([red, green, blue]),
+                _ => return Err("colour() must return [red, green, blue]".to_owned()),
+            }
+        } else {
+            None
+        };
+        self.materials.retain(|(other, _)| *other != cell_type);
+        self.materials.push((
+            cell_type,
+            Script {
+                name: name.to_owned(),
+                ast,
+                colour,
+            },
+        ));
+        Ok(())
+    }
+
+    // Returns the name of the material given by the script for a custom material, if it has one
+    pub fn name(&self, cell_type: CellType) -> Option<&str> {
+        self.script(cell_type).map(|script| script.name.as_str())
+    }
+
+    // Returns the colour given by the script for a custom material, if it has one (as red, green and blue components)
+    pub fn colour(&self, cell_type: CellType) -> Option<[f32; 3]> {
+        self.script(cell_type).and_then(|script| script.colour)
+    }
+
+    fn script(&self, cell_type: CellType) -> Option<&Script> {
+        self.materials
+            .iter()
+            .find(|(other, _)| *other == cell_type)
+            .map(|(_, script)| script)
+    }
+
+    fn call<T: Clone + Send + Sync + 'static>(
+        &self,
+        ast: &AST,
+        function: &str,
+        arguments: impl rhai::FuncArgs,
+    ) -> Result<T, String> {
+        self.engine
+            .call_fn_with_options(
+                CallFnOptions::new().eval_ast(false),
+                &mut Scope::new(),
+                ast,
+                function,
+                arguments,
+            )
+            .map_err(|error| error.to_string())
+    }
+
+    // Returns what a cell does, as decided by its material's script (nothing if it fails), with the script drawing
+    // random numbers from the given generator
+    fn update(&self, cell_type: CellType, cells: Array, rng: &mut StdRng) -> Vec<Action> {
+        let Some(script) = self.script(cell_type) else {
+            return Vec::new();
+        };
+        mem::swap(&mut *self.rng.lock().unwrap(), rng);
+        let material = self.identifiers[cell_type as usize].clone();
+        let result = self.call::<Dynamic>(&script.ast, "update", (cells, material));
+        mem::swap(&mut *self.rng.lock().unwrap(), rng);
+        result.ok().and_then(actions).unwrap_or_default()
+    }
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Scripts::new()
+    }
+}
+
+// Reads the actions returned by a script's update function
+fn actions(result: Dynamic) -> Option<Vec<Action>> {
+    if result.is_unit() {
+        return Some(Vec::new());
+    }
+    let map = result.try_cast::<Map>()?;
+    let mut actions = Vec::new();
+    if let Some(set) = map.get("set") {
+        match set.clone().into_array().ok()?.as_slice() {
+            [down, across, cell_type] => actions.push(Action::Set(
+                offset(down)?,
+                offset(across)?,
+                parse(cell_type)?,
+            )),
+            _ => return None,
+        }
+    }
+    if let Some(cell_type) = map.get("become") {
+        actions.push(Action::Become(parse(cell_type)?));
+    }
+    if let Some(swap) = map.get("swap") {
+        match swap.clone().into_array().ok()?.as_slice() {
+            [down, across] => actions.push(Action::Swap(offset(down)?, offset(across)?)),
+            _ => return None,
+        }
+    }
+    Some(actions)
+}
+
+fn offset(value: &Dynamic) -> Option<isize> {
+    value
+        .as_int()
+        .ok()
+        .filter(|offset| (-1..=1).contains(offset))
+        .map(|offset| offset as isize)
+}
+
+fn parse(value: &Dynamic) -> Option<CellType> {
+    CellType::from_identifier(&value.clone().into_string().ok()?)
+}
+
+impl World {
+    pub(super) fn update_scripted(&mut self, row: usize, column: usize) {
+        let Some(scripts) = self.scripts.clone() else {
+            return;
+        };
+        let cell_type = self.grid.cells[row][column].cell_type;
+        self.wake(row, column);
+        let mut cells = Array::new();
+        for down in -1..=1 {
+            let mut cells_row = Array::new();
+            for across in -1..=1 {
+                let neighbour_type = match self.relative(row, column, down, across) {
+                    Some((neighbour_row, neighbour_column)) => {
+                        self.grid.cells[neighbour_row][neighbour_column].cell_type
+                    }
+                    None => CellType::Wall,
+                };
+                cells_row.push(scripts.identifiers[neighbour_type as usize].clone());
+            }
+            cells.push(cells_row.into());
+        }
+        let actions = scripts.update(cell_type, cells, &mut self.rng);
+        for action in actions {
+            match action {
+                Action::Set(down, across, new_type) => {
+                    if let Some((other_row, other_column)) =
+                        self.relative(row, column, down, across)
+                    {
+                        let other_type = self.grid.cells[other_row][other_column].cell_type;
+                        if !self.immune.contains(&other_type)
+                            && other_type.density().is_some()
+                            && !self.is_updated(other_row, other_column)
+                        {
+                            self.grid.cells[other_row][other_column].reset(new_type);
+                            self.touch(other_row, other_column);
+                        }
+                    }
+                }
+                Action::Become(new_type) => {
+                    self.grid.cells[row][column].reset(new_type);
+                    self.touch(row, column);
+                }
+                Action::Swap(down, across) => {
+                    if let Some((other_row, other_column)) =
+                        self.relative(row, column, down, across)
+                    {
+                        let other_type = self.grid.cells[other_row][other_column].cell_type;
+                        if (other_row, other_column) != (row, column)
+                            && other_type.density().is_some()
+                            && !self.is_updated(other_row, other_column)
+                        {
+                            self.swap(row, column, other_row, other_column);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
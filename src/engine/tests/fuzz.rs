@@ -0,0 +1,161 @@
+// Property-based tests, which step randomly generated worlds of every cell type and size (including 1 x 1, 1 x N and
+// N x 1 worlds) and check that updating never panics and that cells are only ever created according to the rules. A
+// failing case is shrunk to a minimal world before it is reported.
+
+use super::super::*;
+use proptest::prelude::*;
+use proptest::sample::select;
+use std::fmt;
+
+const MAX_SIZE: usize = 40; // Larger than a chunk, so that worlds of more than one chunk are generated
+const MAX_STEPS: usize = 30;
+
+struct Case {
+    rows: usize,
+    columns: usize,
+    cells: Vec<CellType>, // Row by row, starting with the bottom row
+    gravity: Gravity,
+    momentum: bool,
+    seed: u64,
+    steps: usize,
+}
+
+impl Case {
+    fn world(&self) -> World {
+        let mut world = World::with_size(self.rows, self.columns);
+        world.seed(self.seed);
+        world.gravity = self.gravity;
+        world.momentum = self.momentum;
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                world.grid.cells[row][column].cell_type = self.cells[row * self.columns + column];
+            }
+        }
+        world
+    }
+}
+
+// Shows the world in the text format, so that a shrunk case can be read (and turned into a snapshot scenario)
+impl fmt::Debug for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} x {} world, gravity {:?}, momentum {}, seed {}, {} steps:",
+            self.rows, self.columns, self.gravity, self.momentum, self.seed, self.steps
+        )?;
+        write!(f, "{}", self.world().to_text())
+    }
+}
+
+fn sizes() -> impl Strategy<Value = (usize, usize)> {
+    prop_oneof![
+        Just((1, 1)),
+        (1..=MAX_SIZE).prop_map(|columns| (1, columns)),
+        (1..=MAX_SIZE).prop_map(|rows| (rows, 1)),
+        (1..=MAX_SIZE, 1..=MAX_SIZE),
+    ]
+}
+
+fn cases() -> impl Strategy<Value = Case> {
+    let gravities = vec![
+        Gravity::Down,
+        Gravity::Up,
+        Gravity::Left,
+        Gravity::Right,
+        Gravity::Off,
+    ];
+    (
+        sizes(),
+        select(gravities),
+        any::<bool>(),
+        any::<u64>(),
+        1..=MAX_STEPS,
+    )
+        .prop_flat_map(|((rows, columns), gravity, momentum, seed, steps)| {
+            prop::collection::vec(select(CellType::ALL.to_vec()), rows * columns).prop_map(
+                move |cells| Case {
+                    rows,
+                    columns,
+                    cells,
+                    gravity,
+                    momentum,
+                    seed,
+                    steps,
+                },
+            )
+        })
+}
+
+fn counts(world: &World) -> [usize; CellType::ALL.len()] {
+    let mut counts = [0; CellType::ALL.len()];
+    for cell in world.grid.cells.iter().flatten() {
+        counts[cell.cell_type as usize] += 1;
+    }
+    counts
+}
+
+// Returns whether cells of the given type can be created in a step, given the number of cells of each type at the start
+// of the step and whether any events are waiting to be processed
+fn can_create(
+    world: &World,
+    cell_type: CellType,
+    counts: &[usize; CellType::ALL.len()],
+    events: bool,
+) -> bool {
+    let present = |other: CellType| counts[other as usize] > 0;
+    // Cells that are used up leave empty cells behind
+    cell_type == CellType::Empty
+        // Reactions
+        || world.reactions.iter().any(|reaction| {
+            (reaction.cell_becomes == cell_type || reaction.neighbour_becomes == cell_type)
+                && present(reaction.cell)
+                && present(reaction.neighbour)
+        })
+        // Gases dissipating
+        || CellType::ALL.iter().any(|&gas| {
+            present(gas) && gas.gas().is_some_and(|gas| gas.decays_into == cell_type)
+        })
+        // Fire burning out
+        || (cell_type == CellType::Smoke && present(CellType::Fire))
+        // Seeds sprouting and plants growing
+        || (cell_type == CellType::Plant
+            && (present(CellType::Seed) || present(CellType::Plant))
+            && present(CellType::Water))
+        // Explosions, which are either waiting to be processed or set off by fire
+        || (cell_type == CellType::Fire
+            && (events
+                || (present(CellType::Fire)
+                    && (present(CellType::Gunpowder) || present(CellType::Tnt)))))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(500))]
+
+    #[test]
+    fn updates_follow_the_rules(case in cases()) {
+        let mut world = case.world();
+        for step in 1..=case.steps {
+            let before = counts(&world);
+            let events = !world.events.is_empty();
+            world.update();
+            let after = counts(&world);
+            for cell_type in CellType::ALL {
+                prop_assert!(
+                    after[cell_type as usize] <= before[cell_type as usize]
+                        || can_create(&world, cell_type, &before, events),
+                    "Step {}: {:?} cells were created ({} to {}) without anything to create them from",
+                    step,
+                    cell_type,
+                    before[cell_type as usize],
+                    after[cell_type as usize]
+                );
+            }
+            prop_assert_eq!(
+                after[CellType::Wall as usize],
+                before[CellType::Wall as usize],
+                "Step {}: walls were created or destroyed",
+                step
+            );
+        }
+    }
+}
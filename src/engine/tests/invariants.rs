@@ -0,0 +1,150 @@
+// These tests run seeded simulations of randomly filled worlds and check after every step that:
+//     - every cell that changed is marked as updated, so that no cell can be updated a second time in the same step;
+//     - cells of the fixed types (e.g. walls) never move or change;
+//     - the number of cells of each conserved material is unchanged. Materials that turn into one another are counted
+//       together, e.g. water and steam (which condenses back into water) are conserved as one material.
+
+use super::super::*;
+use super::random_world;
+
+const WORLD_SIZE: usize = 32;
+const STEPS: usize = 200;
+const SEEDS: [u64; 3] = [1, 2, 3];
+const GRAVITIES: [Gravity; 5] = [
+    Gravity::Down,
+    Gravity::Up,
+    Gravity::Left,
+    Gravity::Right,
+    Gravity::Off,
+];
+
+fn count(world: &World, cell_types: &[CellType]) -> usize {
+    world
+        .grid
+        .cells
+        .iter()
+        .flatten()
+        .filter(|cell| cell_types.contains(&cell.cell_type))
+        .count()
+}
+
+fn check_invariants(mut world: World, fixed: &[CellType], conserved: &[&[CellType]]) {
+    let counts: Vec<usize> = conserved
+        .iter()
+        .map(|cell_types| count(&world, cell_types))
+        .collect();
+    for step in 1..=STEPS {
+        let before: Vec<Vec<CellType>> = world
+            .grid
+            .cells
+            .iter()
+            .map(|cells| cells.iter().map(|cell| cell.cell_type).collect())
+            .collect();
+        world.update();
+        for (row, cell_types) in before.iter().enumerate() {
+            for (column, &old) in cell_types.iter().enumerate() {
+                let new = world.grid.cells[row][column].cell_type;
+                assert!(
+                    old == new || world.is_updated(row, column),
+                    "Step {}: cell ({}, {}) changed from {:?} to {:?} without being marked as updated",
+                    step,
+                    row,
+                    column,
+                    old,
+                    new
+                );
+                assert!(
+                    old == new || !(fixed.contains(&old) || fixed.contains(&new)),
+                    "Step {}: fixed cell ({}, {}) changed from {:?} to {:?}",
+                    step,
+                    row,
+                    column,
+                    old,
+                    new
+                );
+            }
+        }
+        for (cell_types, &initial) in conserved.iter().zip(&counts) {
+            assert_eq!(
+                count(&world, cell_types),
+                initial,
+                "Step {}: the number of {:?} cells is not conserved",
+                step,
+                cell_types
+            );
+        }
+    }
+}
+
+#[test]
+fn powders_and_liquids_are_conserved() {
+    // Without anything to react with, powders and liquids only ever move, and walls and wood stay where they are
+    let materials = [
+        (CellType::Empty, 8),
+        (CellType::Wall, 1),
+        (CellType::Wood, 1),
+        (CellType::Sand, 2),
+        (CellType::Snow, 1),
+        (CellType::Gunpowder, 1),
+        (CellType::Water, 2),
+        (CellType::Oil, 2),
+    ];
+    for gravity in GRAVITIES {
+        for momentum in [true, false] {
+            for seed in SEEDS {
+                let mut world = random_world(WORLD_SIZE, seed, &materials);
+                world.gravity = gravity;
+                world.momentum = momentum;
+                check_invariants(
+                    world,
+                    &[CellType::Wall, CellType::Wood],
+                    &[
+                        &[CellType::Sand],
+                        &[CellType::Snow],
+                        &[CellType::Gunpowder],
+                        &[CellType::Water],
+                        &[CellType::Oil],
+                    ],
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn water_is_conserved_through_steam() {
+    // Steam condenses back into water, so together they are conserved
+    let materials = [
+        (CellType::Empty, 4),
+        (CellType::Wall, 1),
+        (CellType::Water, 2),
+        (CellType::Steam, 3),
+    ];
+    for gravity in GRAVITIES {
+        for seed in SEEDS {
+            let mut world = random_world(WORLD_SIZE, seed, &materials);
+            world.gravity = gravity;
+            check_invariants(
+                world,
+                &[CellType::Wall],
+                &[&[CellType::Water, CellType::Steam]],
+            );
+        }
+    }
+}
+
+#[test]
+fn walls_withstand_everything() {
+    // With every material present (so that things burn, dissolve and explode), walls still never change
+    let mut materials = vec![(CellType::Empty, 20)];
+    for code in 1..=20 {
+        materials.push((CellType::from_u8(code), 1));
+    }
+    for gravity in GRAVITIES {
+        for seed in SEEDS {
+            let mut world = random_world(WORLD_SIZE, seed, &materials);
+            world.gravity = gravity;
+            check_invariants(world, &[CellType::Wall], &[]);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+mod fuzz;
+mod invariants;
+mod rules;
+#[cfg(feature = "scripting")]
+mod scripts;
+
+use super::*;
+
+// Returns the number of cells of the given type on either side of the centre line of the world, taking the sides to be
+// across the direction of gravity
+fn count_either_side(world: &World, cell_type: CellType) -> (usize, usize) {
+    let centre = world.world_size / 2;
+    let (mut first, mut second) = (0, 0);
+    for row in 0..world.grid.rows {
+        for column in 0..world.grid.columns {
+            if world.grid.cells[row][column].cell_type == cell_type {
+                let across = match world.gravity {
+                    Gravity::Left | Gravity::Right => row,
+                    _ => column,
+                };
+                if across < centre {
+                    first += 1;
+                } else if across > centre {
+                    second += 1;
+                }
+            }
+        }
+    }
+    (first, second)
+}
+
+// Creates a seeded world of the given size filled at random with the given materials, in proportion to their weights
+fn random_world(size: usize, seed: u64, materials: &[(CellType, u32)]) -> World {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let total: u32 = materials.iter().map(|&(_, weight)| weight).sum();
+    let mut world = World::new(size);
+    world.seed(seed);
+    for row in 0..world.grid.rows {
+        for column in 0..world.grid.columns {
+            let mut choice = rng.gen_range(0..total);
+            for &(cell_type, weight) in materials {
+                if choice < weight {
+                    world.grid.cells[row][column].cell_type = cell_type;
+                    break;
+                }
+                choice -= weight;
+            }
+        }
+    }
+    world
+}
+
+#[test]
+fn spreading_is_symmetric() {
+    // Pour a block of each material onto the middle of the floor and check that, over many runs, as much of it ends up
+    // on one side as the other
+    for gravity in [Gravity::Down, Gravity::Up, Gravity::Left, Gravity::Right] {
+        for cell_type in [
+            CellType::Sand,
+            CellType::Water,
+            CellType::Oil,
+            CellType::Steam,
+        ] {
+            let (mut first, mut second) = (0, 0);
+            for seed in 0..10 {
+                let mut world = World::new(33);
+                world.seed(seed);
+                world.gravity = gravity;
+                // A block 5 cells wide, centred across the direction of gravity
+                for height in 10..24 {
+                    for across in 14..19 {
+                        let (row, column) = match gravity {
+                            Gravity::Left | Gravity::Right => (across, height),
+                            _ => (height, across),
+                        };
+                        world.grid.cells[row][column].cell_type = cell_type;
+                    }
+                }
+                for _ in 0..60 {
+                    world.update();
+                }
+                let (this_first, this_second) = count_either_side(&world, cell_type);
+                first += this_first;
+                second += this_second;
+            }
+            let imbalance = first.abs_diff(second) as f32 / (first + second) as f32;
+            assert!(
+                imbalance < 0.1,
+                "{:?} with gravity {:?} is biased to one side ({} vs {})",
+                cell_type,
+                gravity,
+                first,
+                second
+            );
+        }
+    }
+}
+
+#[test]
+fn settled_chunks_sleep() {
+    // Drop a block of sand onto the floor and check that, once it has settled, every chunk goes to sleep and that
+    // painting a cell wakes only the chunk it is in
+    let mut world = World::new(96);
+    for row in 40..60 {
+        for column in 40..60 {
+            world.grid.cells[row][column].cell_type = CellType::Sand;
+        }
+    }
+    for _ in 0..500 {
+        world.update();
+    }
+    let awake_chunks = |world: &World| {
+        let mut awake = Vec::new();
+        for chunk_row in 0..world.chunks.rows {
+            for chunk_column in 0..world.chunks.columns {
+                if world.chunks.is_awake(chunk_row, chunk_column) {
+                    awake.push((chunk_row, chunk_column));
+                }
+            }
+        }
+        awake
+    };
+    assert!(awake_chunks(&world).is_empty());
+    world.set_cell(80, 10, CellType::Sand);
+    world.update();
+    assert_eq!(awake_chunks(&world), vec![(2, 0)]);
+    assert_eq!(world.grid.cells[80][10].cell_type, CellType::Empty);
+}
+
+#[test]
+fn cells_are_packed() {
+    assert_eq!(std::mem::size_of::<Cell>(), 5);
+}
+
+#[test]
+fn worlds_can_be_shared_between_threads() {
+    // Hosts may step a world on a worker thread, and the parallel update relies on it too
+    fn is_send_and_sync<T: Send + Sync>() {}
+    is_send_and_sync::<World>();
+}
+
+#[test]
+fn shades_move_with_cells() {
+    let mut world = World::new(32);
+    world.seed(1);
+    for column in 0..32 {
+        world.set_cell(31, column, CellType::Sand);
+    }
+    let shades: Vec<u8> = (0..32)
+        .map(|column| world.grid.cells[31][column].shade)
+        .collect();
+    // The shades are not all the same
+    assert!(shades.iter().any(|&shade| shade != shades[0]));
+    // Each grain falls straight down without sliding, so it lands in the same column with the same shade
+    world.momentum = false;
+    for _ in 0..40 {
+        world.update();
+    }
+    let landed: Vec<u8> = (0..32)
+        .map(|column| world.grid.cells[0][column].shade)
+        .collect();
+    assert_eq!(landed, shades);
+}
+
+#[test]
+fn new_cells_are_at_rest() {
+    // Drop a grain of sand until it is falling fast, then replace it by painting over it or clearing the world, and
+    // check that the new cell does not keep the grain's velocity
+    let falling_sand = || {
+        let mut world = World::new(64);
+        world.seed(1);
+        world.momentum = true;
+        world.set_cell(63, 10, CellType::Sand);
+        for _ in 0..5 {
+            world.update();
+        }
+        let row = (0..64)
+            .find(|&row| world.grid.cells[row][10].cell_type == CellType::Sand)
+            .unwrap();
+        assert_ne!(world.grid.cells[row][10].velocity_y, 0);
+        (world, row)
+    };
+    let (mut world, row) = falling_sand();
+    world.set_cell(row, 10, CellType::Water);
+    assert_eq!(world.grid.cells[row][10].velocity_y, 0);
+    let (mut world, row) = falling_sand();
+    world.clear();
+    assert_eq!(world.grid.cells[row][10].velocity_y, 0);
+}
+
+#[test]
+fn reacting_cells_are_left_at_rest() {
+    // Boil a moving water cell with lava, and check that the steam it turns into does not keep the water's velocity
+    let mut world = World::new(8);
+    world.seed(1);
+    world.set_cell(4, 4, CellType::Lava);
+    world.set_cell(4, 5, CellType::Water);
+    world.grid.cells[4][5].velocity_x = 40;
+    world.grid.cells[4][5].velocity_y = -60;
+    // React as in the middle of a step, in which neither cell has changed yet
+    world.stamp = 1;
+    world.index_reactions();
+    let mut attempts = 0;
+    while !world.react(4, 4) {
+        attempts += 1;
+        assert!(attempts < 1000, "The lava never reacted");
+    }
+    let steam = world.grid.cells[4][5];
+    assert_eq!(steam.cell_type, CellType::Steam);
+    assert_eq!((steam.velocity_x, steam.velocity_y), (0, 0));
+}
+
+#[test]
+fn debris_keeps_its_shade() {
+    // Blow up TNT buried in sand of a single shade, and check that the sand thrown clear of the blast keeps it
+    let mut world = World::new(32);
+    world.seed(1);
+    for row in 0..12 {
+        for column in 0..32 {
+            world.set_cell(row, column, CellType::Sand);
+            world.grid.cells[row][column].shade = 7;
+        }
+    }
+    world.set_cell(6, 16, CellType::Tnt);
+    world.set_cell(7, 16, CellType::Fire);
+    for _ in 0..5 {
+        world.update();
+    }
+    let mut sand = 0;
+    for row in 0..32 {
+        for column in 0..32 {
+            let cell = world.grid.cells[row][column];
+            if cell.cell_type == CellType::Sand {
+                sand += 1;
+                assert_eq!(cell.shade, 7);
+            }
+        }
+    }
+    // Some of the sand was blown away rather than thrown clear
+    assert!(sand < 12 * 32 - 2);
+}
+
+#[test]
+fn clearing_stops_chained_explosions() {
+    // Set fire to a row of gunpowder, whose explosions set off the rest of it in the following steps, clear the world
+    // while explosions are still queued, and check that nothing more happens
+    let mut world = World::new(64);
+    world.seed(1);
+    world.momentum = false;
+    for column in 0..64 {
+        world.set_cell(0, column, CellType::Gunpowder);
+    }
+    world.set_cell(1, 0, CellType::Fire);
+    let mut steps = 0;
+    while world.events.is_empty() {
+        world.update();
+        steps += 1;
+        assert!(steps < 100, "The gunpowder never exploded");
+    }
+    world.clear();
+    for _ in 0..10 {
+        world.update();
+    }
+    for row in 0..64 {
+        for column in 0..64 {
+            assert_eq!(world.grid.cells[row][column].cell_type, CellType::Empty);
+        }
+    }
+}
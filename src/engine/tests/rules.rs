@@ -0,0 +1,101 @@
+// Tests of the rules language (see the rules module)
+
+use super::super::*;
+use super::random_world;
+
+const CLASSIC_RULES: &str = include_str!("classic.rules");
+const WORLD_SIZE: usize = 40;
+const STEPS: usize = 300;
+
+// Fills a world with the materials the classic rules describe, and walls for them to run into
+fn random_classic_world(seed: u64) -> World {
+    let materials = [
+        (CellType::Empty, 3),
+        (CellType::Wall, 1),
+        (CellType::Sand, 1),
+        (CellType::Water, 1),
+        (CellType::Oil, 1),
+        (CellType::Steam, 1),
+    ];
+    let mut world = random_world(WORLD_SIZE, seed, &materials);
+    world.momentum = false;
+    world
+}
+
+#[test]
+fn rules_reproduce_built_in_materials() {
+    let rules = Arc::new(Rules::parse(CLASSIC_RULES).unwrap());
+    for gravity in [
+        Gravity::Down,
+        Gravity::Up,
+        Gravity::Left,
+        Gravity::Right,
+        Gravity::Off,
+    ] {
+        for seed in [1, 2, 3] {
+            let mut built_in = random_classic_world(seed);
+            let mut ruled = random_classic_world(seed);
+            built_in.gravity = gravity;
+            ruled.gravity = gravity;
+            ruled.rules = Some(rules.clone());
+            for step in 1..=STEPS {
+                built_in.update();
+                ruled.update();
+                assert!(
+                    built_in.to_text() == ruled.to_text(),
+                    "Gravity {:?}, seed {}: the worlds differ after step {}\nBuilt in:\n{}\nRules:\n{}",
+                    gravity,
+                    seed,
+                    step,
+                    built_in.to_text(),
+                    ruled.to_text()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    for (rules, error) in [
+        (
+            "sand down is empty => swap down",
+            "Line 1: Expected ':' after the materials",
+        ),
+        (
+            "sand : down is empty",
+            "Line 1: Expected '=>' before the actions",
+        ),
+        (
+            "\nquicksand : => swap down",
+            "Line 2: Unknown cell type 'quicksand'",
+        ),
+        (
+            "sand : below is empty => swap down",
+            "Line 1: Unknown position 'below'",
+        ),
+        (
+            "sand : down is heavy => swap down",
+            "Line 1: Unknown class 'heavy'",
+        ),
+        (
+            "sand : down empty => swap down",
+            "Line 1: Expected a condition of the form '<position> is <classes>', not 'down empty'",
+        ),
+        (
+            "sand : => here <- down, down <- here, here <- left",
+            "Line 1: A position is changed by more than one action",
+        ),
+        (
+            "sand : => swap down sometimes",
+            "Line 1: Unknown option 'sometimes'",
+        ),
+    ] {
+        assert_eq!(
+            Rules::parse(rules).err().as_deref(),
+            Some(error),
+            "{}",
+            rules
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// Tests of custom materials defined by scripts (see the scripts module)
+
+use super::super::*;
+
+// Falls straight down, and turns the water it lands on into stone
+const SCRIPT: &str = r#"
+    fn update(cells, material) {
+        if cells[2][1] == "empty" {
+            #{ swap: [1, 0] }
+        } else if cells[2][1] == "water" && random() < 0.5 {
+            #{ set: [1, 0, "stone"], become: "sand" }
+        }
+    }
+
+    fn colour() {
+        [0.1, 0.2, 0.3]
+    }
+"#;
+
+fn scripted_world(text: &[&str]) -> World {
+    let mut scripts = Scripts::new();
+    scripts
+        .load(CellType::Custom1, "petrifier", SCRIPT)
+        .unwrap();
+    let mut world = World::from_text(&text.join("\n")).unwrap();
+    world.seed(1);
+    world.scripts = Some(Arc::new(scripts));
+    world
+}
+
+#[test]
+fn scripts_define_behaviour() {
+    let mut world = scripted_world(&["A..A", "....", "#...", "#www"]);
+    for _ in 0..20 {
+        world.update();
+    }
+    // The cell over the wall comes to rest on it, while the other lands on the water, turning the water under it into
+    // stone and itself into sand (which then sinks through the rest of the water)
+    assert_eq!(world.to_text(), "....\nA...\n#..w\n#wsS\n");
+    let scripts = world.scripts.as_ref().unwrap();
+    assert_eq!(scripts.name(CellType::Custom1), Some("petrifier"));
+    assert_eq!(scripts.colour(CellType::Custom1), Some([0.1, 0.2, 0.3]));
+    assert_eq!(scripts.name(CellType::Custom2), None);
+}
+
+#[test]
+fn scripts_cannot_break_the_rules() {
+    // A script that tries to move a wall, destroy a wall and make an unknown material does nothing
+    let mut scripts = Scripts::new();
+    let script = r#"
+        fn update(cells, material) {
+            let random = random();
+            if random < 0.3 { #{ swap: [1, 0] } }
+            else if random < 0.6 { #{ set: [1, 0, "empty"] } }
+            else { #{ become: "unobtainium" } }
+        }
+    "#;
+    scripts.load(CellType::Custom2, "vandal", script).unwrap();
+    let mut world = World::from_text("B\n#").unwrap();
+    world.scripts = Some(Arc::new(scripts));
+    for _ in 0..20 {
+        world.update();
+    }
+    assert_eq!(world.to_text(), "B\n#\n");
+}
+
+#[test]
+fn scripts_cannot_overwrite_static_or_updated_cells() {
+    // A script that tries to empty the cells to its right and below it
+    let mut scripts = Scripts::new();
+    let script = r#"
+        fn update(cells, material) {
+            #{ set: [0, 1, "empty"] }
+        }
+    "#;
+    scripts.load(CellType::Custom1, "eraser", script).unwrap();
+    let script = r#"
+        fn update(cells, material) {
+            #{ set: [1, 0, "stone"] }
+        }
+    "#;
+    scripts.load(CellType::Custom2, "paver", script).unwrap();
+    let scripts = Arc::new(scripts);
+    // Wood is static, so it cannot be erased
+    let mut world = World::from_text("AW").unwrap();
+    world.scripts = Some(scripts.clone());
+    world.update();
+    assert_eq!(world.to_text(), "AW\n");
+    // The sand falls out from under the script's cell before it is updated, so the cell it leaves has already changed
+    // in this step and cannot be paved, until the next step
+    let mut world = World::from_text("B\ns\n.").unwrap();
+    world.momentum = false;
+    world.scripts = Some(scripts);
+    world.update();
+    assert_eq!(world.to_text(), "B\n.\ns\n");
+    world.update();
+    assert_eq!(world.to_text(), "B\nS\ns\n");
+}
+
+#[test]
+fn scripts_know_their_own_material() {
+    // The example slime turns water into more of itself, whichever custom material it is loaded as
+    let mut scripts = Scripts::new();
+    scripts
+        .load(
+            CellType::Custom3,
+            "slime",
+            include_str!("../../../materials/slime.rhai"),
+        )
+        .unwrap();
+    let mut world = World::from_text("C...\nwwww").unwrap();
+    world.seed(1);
+    world.scripts = Some(Arc::new(scripts));
+    for _ in 0..200 {
+        world.update();
+    }
+    let text = world.to_text();
+    assert!(!text.contains('w') && !text.contains('A'), "{}", text);
+    assert_eq!(text.matches('C').count(), 5);
+}
+
+#[test]
+fn invalid_scripts_are_rejected() {
+    let mut scripts = Scripts::new();
+    assert!(scripts.load(CellType::Sand, "sand", SCRIPT).is_err());
+    assert!(scripts
+        .load(CellType::Custom1, "syntax", "fn update(")
+        .is_err());
+    assert!(scripts
+        .load(
+            CellType::Custom1,
+            "no update",
+            "fn colour() { [1.0, 1.0, 1.0] }"
+        )
+        .is_err());
+    assert!(scripts
+        .load(
+            CellType::Custom1,
+            "bad colour",
+            "fn update(cells, material) {} fn colour() { 1 }"
+        )
+        .is_err());
+    // A script that never finishes is stopped rather than hanging the simulation
+    scripts
+        .load(
+            CellType::Custom1,
+            "loop",
+            "fn update(cells, material) { loop {} }",
+        )
+        .unwrap();
+    let mut world = World::from_text("A").unwrap();
+    world.scripts = Some(Arc::new(scripts));
+    world.update();
+    assert_eq!(world.to_text(), "A\n");
+}
@@ -0,0 +1,103 @@
+// This module defines a plain text format for worlds, used to write scenarios and snapshots by hand.
+//
+// Each line of text is a row of the world, starting with the top row (the last row of the grid), and each character is
+// a cell, given by the character for its cell type (see CellType::to_char). Every row must be the same width.
+
+use super::{CellType, World};
+
+impl CellType {
+    pub fn to_char(self) -> char {
+        match self {
+            CellType::Empty => '.',
+            CellType::Wall => '#',
+            CellType::Wood => 'W',
+            CellType::Sand => 's',
+            CellType::Water => 'w',
+            CellType::Oil => 'o',
+            CellType::Steam => '~',
+            CellType::Lava => 'L',
+            CellType::Stone => 'S',
+            CellType::Obsidian => 'O',
+            CellType::Fire => 'f',
+            CellType::Acid => 'a',
+            CellType::Glass => 'G',
+            CellType::Ice => 'I',
+            CellType::Snow => '*',
+            CellType::Seed => ',',
+            CellType::Plant => 'p',
+            CellType::Gunpowder => 'g',
+            CellType::Tnt => 'T',
+            CellType::Smoke => 'm',
+            CellType::ToxicGas => 't',
+            CellType::Custom1 => 'A',
+            CellType::Custom2 => 'B',
+            CellType::Custom3 => 'C',
+            CellType::Custom4 => 'D',
+        }
+    }
+
+    pub fn from_char(character: char) -> Option<CellType> {
+        CellType::ALL
+            .into_iter()
+            .find(|cell_type| cell_type.to_char() == character)
+    }
+
+    // Returns the name of the cell type as a single lower case word (e.g. 'toxic_gas'), for use in commands and scripts
+    pub fn identifier(self) -> String {
+        self.name().to_lowercase().replace(' ', "_")
+    }
+
+    pub fn from_identifier(identifier: &str) -> Option<CellType> {
+        CellType::ALL
+            .into_iter()
+            .find(|cell_type| cell_type.identifier() == identifier)
+    }
+}
+
+impl World {
+    // Creates a world from its text representation, ignoring blank lines and leading and trailing whitespace
+    pub fn from_text(text: &str) -> Result<World, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let rows = lines.len();
+        if rows == 0 {
+            return Err("The world is empty".to_owned());
+        }
+        let columns = lines[0].chars().count();
+        let mut world = World::with_size(rows, columns);
+        for (line_number, line) in lines.iter().enumerate() {
+            let row = rows - 1 - line_number;
+            if line.chars().count() != columns {
+                return Err(format!(
+                    "Line {} is {} cells wide, but the first line is {} cells wide",
+                    line_number + 1,
+                    line.chars().count(),
+                    columns
+                ));
+            }
+            for (column, character) in line.chars().enumerate() {
+                world.grid.cells[row][column].cell_type =
+                    CellType::from_char(character).ok_or(format!(
+                        "Unknown cell type '{}' on line {}",
+                        character,
+                        line_number + 1
+                    ))?;
+            }
+        }
+        Ok(world)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row in (0..self.grid.rows).rev() {
+            for column in 0..self.grid.columns {
+                text.push(self.grid.cells[row][column].cell_type.to_char());
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
@@ -2,7 +2,25 @@
 //
 // A world is defined using a tuple of the form (world size, 3D world array) where:
 //     - The array width and height is the world size;
-//     - The array values are either 0, 1, 2, 3, 4, 5 or 6, representing EMPTY, WALL, WOOD, SAND, WATER, OIL or STEAM cell types respectively
+//     - The array values are either 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22,
+//       23 or 24, representing EMPTY, WALL, WOOD, SAND, WATER, OIL, STEAM, LAVA, STONE, OBSIDIAN, FIRE, ACID, GLASS,
+//       ICE, SNOW, SEED, PLANT, GUNPOWDER, TNT, SMOKE, TOXIC GAS or CUSTOM 1 to 4 cell types respectively (the codes of
+//       CellType::ALL)
+
+use crate::engine::{CellType, World};
+
+// Creates a world from an example, scaling it up so that each cell of the example fills a square of cells of the given
+// size
+pub fn populate_world<const N: usize>(example: &(usize, [[u8; N]; N]), scale: usize) -> World {
+    let mut world = World::new(example.0 * scale);
+    for row in 0..world.grid.rows {
+        for column in 0..world.grid.columns {
+            world.grid.cells[row][column].cell_type =
+                CellType::from_u8(example.1[row / scale][column / scale]);
+        }
+    }
+    world
+}
 
 pub const EXAMPLE_1: (usize, [[u8; 128]; 128]) = (
     128,
@@ -0,0 +1,146 @@
+// A C interface to the engine, so that it can be hosted by programs written in other languages (see ffi/rust_fall.h,
+// which documents each function, and ffi/test.c for an example).
+//
+// Worlds are handed out as opaque pointers, which must be freed with rust_fall_world_free. Cell types are passed as
+// their codes (see CellType), and rows are counted from the bottom of the world. Every function that takes a world
+// expects a pointer returned by rust_fall_world_new or rust_fall_world_load that has not been freed, and every buffer
+// must be at least as long as the length passed with it. These requirements are documented in the header rather than
+// on each function.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::engine::{CellType, World};
+use std::ffi::{c_char, CStr};
+use std::ptr;
+use std::slice;
+
+pub const VERSION: u32 = 1; // Version of the C interface, increased whenever it changes incompatibly
+
+#[no_mangle]
+pub extern "C" fn rust_fall_version() -> u32 {
+    VERSION
+}
+
+// Creates an empty world, returning null if either dimension is zero
+#[no_mangle]
+pub extern "C" fn rust_fall_world_new(rows: usize, columns: usize) -> *mut World {
+    if rows == 0 || columns == 0 {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(World::with_size(rows, columns)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_free(world: *mut World) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_rows(world: *const World) -> usize {
+    (*world).grid.rows
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_columns(world: *const World) -> usize {
+    (*world).grid.columns
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_seed(world: *mut World, seed: u64) {
+    (*world).seed(seed);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_step(world: *mut World, steps: usize) {
+    for _ in 0..steps {
+        (*world).update();
+    }
+}
+
+// Returns the code of the cell's type, or -1 if the position is outside the world
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_get_cell(
+    world: *const World,
+    row: usize,
+    column: usize,
+) -> i32 {
+    let world = &*world;
+    if row < world.grid.rows && column < world.grid.columns {
+        world.grid.cells[row][column].cell_type as i32
+    } else {
+        -1
+    }
+}
+
+// Sets the cell's type, returning false (and leaving the world unchanged) if the position is outside the world or the
+// code is not a cell type
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_set_cell(
+    world: *mut World,
+    row: usize,
+    column: usize,
+    cell_type: u8,
+) -> bool {
+    let world = &mut *world;
+    if row >= world.grid.rows
+        || column >= world.grid.columns
+        || cell_type as usize >= CellType::ALL.len()
+    {
+        return false;
+    }
+    world.set_cell(row, column, CellType::from_u8(cell_type));
+    true
+}
+
+// Copies the code of every cell's type into the buffer, row by row starting with the bottom row, and returns the number
+// of cells in the world. Nothing is copied if the buffer is too small, so the buffer's size can be found by passing a
+// length of 0.
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_read_cells(
+    world: *const World,
+    buffer: *mut u8,
+    length: usize,
+) -> usize {
+    let world = &*world;
+    let cells = world.grid.rows * world.grid.columns;
+    if buffer.is_null() || length < cells {
+        return cells;
+    }
+    let buffer = slice::from_raw_parts_mut(buffer, cells);
+    for (output, cell) in buffer.iter_mut().zip(world.grid.cells.iter().flatten()) {
+        *output = cell.cell_type as u8;
+    }
+    cells
+}
+
+// Saves the world in the text format (see the engine's text module) into the buffer, followed by a null character, and
+// returns the length of the text (not counting the null character). As with snprintf, nothing is written if the buffer
+// is too small, so the buffer's size can be found by passing a length of 0.
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_save(
+    world: *const World,
+    buffer: *mut c_char,
+    length: usize,
+) -> usize {
+    let text = (*world).to_text();
+    if !buffer.is_null() && length > text.len() {
+        let buffer = slice::from_raw_parts_mut(buffer as *mut u8, text.len() + 1);
+        buffer[..text.len()].copy_from_slice(text.as_bytes());
+        buffer[text.len()] = 0;
+    }
+    text.len()
+}
+
+// Creates a world from its text representation (a null terminated string), returning null if the text is not a valid
+// world
+#[no_mangle]
+pub unsafe extern "C" fn rust_fall_world_load(text: *const c_char) -> *mut World {
+    if text.is_null() {
+        return ptr::null_mut();
+    }
+    match CStr::from_ptr(text).to_str().map(World::from_text) {
+        Ok(Ok(world)) => Box::into_raw(Box::new(world)),
+        _ => ptr::null_mut(),
+    }
+}
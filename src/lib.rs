@@ -0,0 +1,8 @@
+// The simulation engine, example worlds, cell colours and statistics, shared by the frontends, tests and benchmarks,
+// and a C interface and text protocol for hosting and driving the engine from other programs
+pub mod colours;
+pub mod engine;
+pub mod examples;
+pub mod ffi;
+pub mod protocol;
+pub mod stats;
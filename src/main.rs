@@ -1,9 +1,11 @@
-mod engine;
-mod examples;
-use engine::{CellType, World};
 use macroquad::prelude::*;
 use macroquad::ui::root_ui;
-use std::time;
+#[cfg(feature = "scripting")]
+use rust_fall::engine::Scripts;
+use rust_fall::engine::{CellType, Gravity, Rules, World, CHUNK_SIZE};
+use rust_fall::stats::Statistics;
+use rust_fall::{colours, examples};
+use std::sync::Arc;
 
 const CONTROLS_PANE_HEIGHT: usize = 40; // in pixels
 const WORLD_PANE_SIZE: usize = 512; // in pixels (width and height)
@@ -13,7 +15,7 @@ const TIME_STEP: f32 = 0.02; // in seconds
 
 // Launch window
 fn window_conf() -> Conf {
-    println!("Launching window...");
+    info!("Launching window...");
     Conf {
         window_title: "Rust Fall".to_owned(),
         window_height: WINDOW_HEIGHT as i32,
@@ -26,65 +28,114 @@ fn window_conf() -> Conf {
 #[macroquad::main(window_conf)]
 async fn main() {
     let mut insert: bool;
-    let mut world = populate_world();
-    println!("World initialised");
+    let scenario = load_scenario().await;
+    #[cfg(feature = "scripting")]
+    let scripts = load_scripts();
+    let rules = load_rules();
+    let mut world = new_world(scenario.as_deref());
+    world.rules.clone_from(&rules);
+    #[cfg(feature = "scripting")]
+    world.scripts.clone_from(&scripts);
+    info!("World initialised");
     let mut pixels_per_cell: f32 = WORLD_PANE_SIZE as f32 / world.world_size as f32;
     let mut remainder_ms: i32 = 0;
     let mut counter: usize = 0;
     let mut paused: bool = false;
     let mut material_type: CellType = CellType::Sand;
+    let mut depth_shading: bool = false;
+    let mut world_texture = CellTexture::new(&world);
+    let mut overlays = Overlays::new(&world);
+    let mut stats_panel = StatsPanel::new();
     let time_step_ms: usize = (TIME_STEP * 1000.0) as usize;
-    let start_time = time::Instant::now();
-    let mut time_of_last_update = start_time.elapsed().as_millis();
+    let mut time_of_last_update = elapsed_ms();
     // Initiate loop
     loop {
         // Draw world
-        draw(&world, pixels_per_cell);
+        draw(
+            &world,
+            &mut world_texture,
+            &mut overlays,
+            &stats_panel,
+            pixels_per_cell,
+            depth_shading,
+        );
         // Draw UI
         if root_ui().button(Vec2::new(10.0, 10.), "Pause/resume") {
             paused = !paused;
-            println!("Simulation {}", if paused { "paused" } else { "resumed" });
-            time_of_last_update = start_time.elapsed().as_millis();
-        }
-        if root_ui().button(Vec2::new(108.0, 10.0), "Step") {
-            if paused {
-                world.update();
-                draw(&world, pixels_per_cell);
-                println!("Single step performed");
-            }
+            info!("Simulation {}", if paused { "paused" } else { "resumed" });
+            time_of_last_update = elapsed_ms();
+        }
+        if root_ui().button(Vec2::new(108.0, 10.0), "Step") && paused {
+            step(&mut world, &mut overlays, &mut stats_panel.statistics);
+            draw(
+                &world,
+                &mut world_texture,
+                &mut overlays,
+                &stats_panel,
+                pixels_per_cell,
+                depth_shading,
+            );
+            info!("Single step performed");
         }
         if root_ui().button(Vec2::new(150.0, 10.), "Restart") {
-            world = populate_world();
+            world = new_world(scenario.as_deref());
+            world.rules.clone_from(&rules);
+            #[cfg(feature = "scripting")]
+            world.scripts.clone_from(&scripts);
             pixels_per_cell = WORLD_PANE_SIZE as f32 / world.world_size as f32;
-            println!("Simulation restarted");
+            stats_panel.statistics.clear();
+            info!("Simulation restarted");
         }
         if root_ui().button(Vec2::new(213.0, 10.), "Clear") {
             world.clear();
-            println!("All cells cleared");
-        }
-        if root_ui().button(
-            Vec2::new(262.0, 10.0),
-            match material_type {
-                CellType::Empty => "Empty",
-                CellType::Sand => "Sand",
-                CellType::Water => "Water",
-                CellType::Wall => "Wall",
-                CellType::Steam => "Steam",
-                CellType::Oil => "Oil",
-                CellType::Wood => "Wood",
-            },
-        ) {
+            info!("All cells cleared");
+        }
+        if root_ui().button(Vec2::new(262.0, 10.0), material_type.name()) {
             material_type = toggle(material_type);
-            println!("Material toggled");
+            info!("Material toggled");
         }
         // Button to print current world state to the console - not currently used
         //if root_ui().button(Vec2::new(250.0, 10.0), "Export") {
         //    print_world(&world);
         //}
-        if is_mouse_button_down(MouseButton::Left) {
-            insert = true;
-        } else {
-            insert = false;
+        insert = is_mouse_button_down(MouseButton::Left);
+        // Change the direction of gravity with the arrow keys, switch it off or on with G and change its strength with
+        // the + and - keys
+        for (key, gravity) in [
+            (KeyCode::Down, Gravity::Down),
+            (KeyCode::Up, Gravity::Up),
+            (KeyCode::Left, Gravity::Left),
+            (KeyCode::Right, Gravity::Right),
+        ] {
+            if is_key_pressed(key) {
+                world.gravity = gravity;
+                info!("Gravity set to {:?}", world.gravity);
+            }
+        }
+        if is_key_pressed(KeyCode::G) {
+            world.gravity = if world.gravity == Gravity::Off {
+                Gravity::Down
+            } else {
+                Gravity::Off
+            };
+            info!("Gravity set to {:?}", world.gravity);
+        }
+        overlays.toggle(pixels_per_cell);
+        stats_panel.handle_keys();
+        if is_key_pressed(KeyCode::L) {
+            depth_shading = !depth_shading;
+            info!(
+                "Liquid depth shading {}",
+                if depth_shading { "on" } else { "off" }
+            );
+        }
+        if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            world.gravity_strength += 0.25;
+            info!("Gravity strength set to {}", world.gravity_strength);
+        }
+        if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            world.gravity_strength = (world.gravity_strength - 0.25).max(0.25);
+            info!("Gravity strength set to {}", world.gravity_strength);
         }
         // Update world
         if insert {
@@ -100,18 +151,21 @@ async fn main() {
                 let mut y = (world.world_size as f32
                     - ((pos.1 - CONTROLS_PANE_HEIGHT as f32) / pixels_per_cell))
                     .floor() as usize;
-                if y >= world.world_size as usize {
+                if y >= world.world_size {
                     y = (world.world_size as f32 - 1.0) as usize;
                 }
-                world.grid.cells[y as usize][x as usize].cell_type = material_type;
+                // A scenario that is not square leaves part of the world pane without cells
+                if y < world.grid.rows && x < world.grid.columns {
+                    world.set_cell(y, x, material_type);
+                }
             }
         }
         if !paused {
-            remainder_ms += (start_time.elapsed().as_millis() - time_of_last_update) as i32;
+            remainder_ms += (elapsed_ms() - time_of_last_update) as i32;
             // This 'keep up' updates the world without rendering as many times as necessary to keep up with the target time step
             while remainder_ms > time_step_ms as i32 {
-                world.update();
-                time_of_last_update = start_time.elapsed().as_millis();
+                step(&mut world, &mut overlays, &mut stats_panel.statistics);
+                time_of_last_update = elapsed_ms();
                 remainder_ms -= time_step_ms as i32;
                 counter += 1;
                 // Break out of the 'keep up' loop after 10 consecutive iterations to ensure application responsiveness (simulation not keeping up with target time step)
@@ -126,7 +180,441 @@ async fn main() {
     }
 }
 
-fn draw(world: &World, pixels_per_cell: f32) {
+// Returns the time since the application started, which (unlike std::time::Instant) works on every target, including
+// wasm32
+fn elapsed_ms() -> u128 {
+    (get_time() * 1000.0) as u128
+}
+
+// Returns the text of the scenario to start with, if one was given (see scenario_path)
+async fn load_scenario() -> Option<String> {
+    let path = scenario_path()?;
+    match load_string(&path).await {
+        Ok(text) => {
+            info!("Loaded scenario {}", path);
+            Some(text)
+        }
+        Err(error) => {
+            error!("Could not load scenario {}: {}", path, error);
+            None
+        }
+    }
+}
+
+// Creates a world from the scenario (in the engine's text format), or from the built in example if there is no
+// scenario or it is invalid
+fn new_world(scenario: Option<&str>) -> World {
+    let mut world = match scenario.map(World::from_text) {
+        Some(Ok(world)) => world,
+        Some(Err(error)) => {
+            error!("Invalid scenario: {}", error);
+            examples::populate_world(&examples::EXAMPLE_1, 1)
+        }
+        None => examples::populate_world(&examples::EXAMPLE_1, 1),
+    };
+    // Momentum is off by default in the engine, but makes the window livelier
+    world.momentum = true;
+    // There is no operating system random number generator in the browser, so seed the world from the clock
+    if cfg!(target_arch = "wasm32") {
+        world.seed(macroquad::miniquad::date::now().to_bits());
+    }
+    world
+}
+
+// Loads the scripts for the custom materials from the materials directory, if there is one
+#[cfg(feature = "scripting")]
+fn load_scripts() -> Option<Arc<Scripts>> {
+    let directory = std::path::Path::new(Scripts::DIRECTORY);
+    if !directory.is_dir() {
+        return None;
+    }
+    match Scripts::load_directory(directory) {
+        Ok(scripts) => {
+            for cell_type in CellType::CUSTOM {
+                if let Some(name) = scripts.name(cell_type) {
+                    info!("Loaded {} as {}", name, cell_type.name());
+                }
+            }
+            Some(Arc::new(scripts))
+        }
+        Err(error) => {
+            error!("Could not load scripts: {}", error);
+            None
+        }
+    }
+}
+
+// Loads the rules for materials from the materials directory, if there are any
+fn load_rules() -> Option<Arc<Rules>> {
+    let directory = std::path::Path::new(Rules::DIRECTORY);
+    if !directory.is_dir() {
+        return None;
+    }
+    match Rules::load_directory(directory) {
+        Ok(rules) if rules.is_empty() => None,
+        Ok(rules) => {
+            info!("Loaded rules");
+            Some(Arc::new(rules))
+        }
+        Err(error) => {
+            error!("Could not load rules: {}", error);
+            None
+        }
+    }
+}
+
+// Returns the path of the scenario to start with, given as the first command line argument
+#[cfg(not(target_arch = "wasm32"))]
+fn scenario_path() -> Option<String> {
+    std::env::args().nth(1)
+}
+
+// Returns the path of the scenario to start with, given by the page embedding the simulation (see web/index.html)
+#[cfg(target_arch = "wasm32")]
+fn scenario_path() -> Option<String> {
+    extern "C" {
+        // Writes the path into the buffer and returns its length (0 if there is no scenario)
+        fn rust_fall_scenario(buffer: *mut u8, length: usize) -> usize;
+    }
+    let mut buffer = vec![0; 1024];
+    let length = unsafe { rust_fall_scenario(buffer.as_mut_ptr(), buffer.len()) };
+    if length == 0 || length > buffer.len() {
+        return None;
+    }
+    buffer.truncate(length);
+    String::from_utf8(buffer).ok()
+}
+
+// Lets the page embedding the simulation check that it is compatible (the version of the embedding API, as
+// major << 24 | minor << 16 | patch, which is unrelated to the version of the crate)
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub extern "C" fn rust_fall_embed_api_version() -> u32 {
+    1 << 24
+}
+
+const MIN_GRID_SPACING: f32 = 6.0; // Width of cells (in pixels) from which the grid overlay draws lines between them
+const HEAT_DECAY: f32 = 0.8; // Fraction of its heat that a cell keeps after each step in which it does not change
+
+// An image with one pixel per cell, which is uploaded to the GPU as a texture once per frame and drawn scaled up over
+// the world pane with nearest neighbour filtering, so that the whole world is drawn with a single call however large it
+// is. The world and the overlays that colour cells are each drawn as one.
+struct CellTexture {
+    image: Image,
+    texture: Texture2D,
+}
+
+impl CellTexture {
+    fn new(world: &World) -> Self {
+        let image =
+            Image::gen_image_color(world.grid.columns as u16, world.grid.rows as u16, BLANK);
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+        CellTexture { image, texture }
+    }
+
+    // Redraws the image (as red, green, blue and alpha bytes, starting with the top row) and uploads it, replacing the
+    // texture if the world has changed size
+    fn update(&mut self, world: &World, write: impl FnOnce(&mut [u8])) {
+        if self.image.width() != world.grid.columns || self.image.height() != world.grid.rows {
+            self.texture.delete();
+            *self = CellTexture::new(world);
+        }
+        write(&mut self.image.bytes);
+        self.texture.update(&self.image);
+    }
+
+    // Draws the texture over the world pane, with the bottom row of cells at the bottom of the pane
+    fn draw(&self, world: &World, pixels_per_cell: f32) {
+        let width = world.grid.columns as f32 * pixels_per_cell;
+        let height = world.grid.rows as f32 * pixels_per_cell;
+        draw_texture_ex(
+            self.texture,
+            0.0,
+            (CONTROLS_PANE_HEIGHT + WORLD_PANE_SIZE) as f32 - height,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(Vec2::new(width, height)),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+// Debug overlays drawn over the world pane to show what the engine is doing, each switched on and off with a key
+struct Overlays {
+    heatmap: bool, // 1: cells that changed recently, brightest for those that changed in the last step
+    chunks: bool,  // 2: outlines of the chunks that were updated in the last step
+    density: bool, // 3: density of the cells that can move, from blue (lightest) to red (densest)
+    grid: bool,    // 4: lines between cells, once they are drawn large enough
+    heat: Vec<f32>, // Heat of each cell (between 0 and 1), row by row starting with the bottom row
+    texture: CellTexture,
+}
+
+impl Overlays {
+    fn new(world: &World) -> Self {
+        Overlays {
+            heatmap: false,
+            chunks: false,
+            density: false,
+            grid: false,
+            heat: Vec::new(),
+            texture: CellTexture::new(world),
+        }
+    }
+
+    // Switches the overlays on and off with the number keys
+    fn toggle(&mut self, pixels_per_cell: f32) {
+        for (key, overlay, name) in [
+            (KeyCode::Key1, &mut self.heatmap, "Heatmap"),
+            (KeyCode::Key2, &mut self.chunks, "Chunk"),
+            (KeyCode::Key3, &mut self.density, "Density"),
+            (KeyCode::Key4, &mut self.grid, "Grid"),
+        ] {
+            if is_key_pressed(key) {
+                *overlay = !*overlay;
+                info!("{} overlay {}", name, if *overlay { "on" } else { "off" });
+            }
+        }
+        if is_key_pressed(KeyCode::Key4) && self.grid && pixels_per_cell < MIN_GRID_SPACING {
+            info!(
+                "Grid hidden until cells are drawn at least {} pixels wide",
+                MIN_GRID_SPACING
+            );
+        }
+    }
+
+    // Heats the cells that changed in the step just performed, and lets the others cool
+    fn record_step(&mut self, world: &World) {
+        if !self.heatmap {
+            return;
+        }
+        let cells = world.grid.rows * world.grid.columns;
+        if self.heat.len() != cells {
+            self.heat = vec![0.0; cells];
+        }
+        for row in 0..world.grid.rows {
+            for column in 0..world.grid.columns {
+                let heat = &mut self.heat[row * world.grid.columns + column];
+                *heat = if world.changed_in_last_step(row, column) {
+                    1.0
+                } else {
+                    *heat * HEAT_DECAY
+                };
+            }
+        }
+    }
+
+    fn draw(&mut self, world: &World, pixels_per_cell: f32) {
+        if self.heatmap || self.density {
+            let heat = &self.heat;
+            let (heatmap, density) = (self.heatmap, self.density);
+            let max_density = CellType::ALL
+                .iter()
+                .filter_map(|cell_type| cell_type.density())
+                .max()
+                .unwrap_or(1) as f32;
+            self.texture.update(world, |pixels| {
+                for (pixel_row, row) in pixels
+                    .chunks_exact_mut(world.grid.columns * 4)
+                    .zip((0..world.grid.rows).rev())
+                {
+                    for (pixel, column) in pixel_row.chunks_exact_mut(4).zip(0..world.grid.columns)
+                    {
+                        let mut colour = BLANK;
+                        if density {
+                            if let Some(density) = world.grid.cells[row][column].cell_type.density()
+                            {
+                                let density = density as f32 / max_density;
+                                colour = Color::new(density, 0.0, 1.0 - density, 0.6);
+                            }
+                        }
+                        let heat = heat
+                            .get(row * world.grid.columns + column)
+                            .copied()
+                            .unwrap_or(0.0);
+                        if heatmap && heat > 0.05 {
+                            colour = Color::new(1.0, heat, 0.0, 0.3 + 0.5 * heat);
+                        }
+                        let bytes: [u8; 4] = colour.into();
+                        pixel.copy_from_slice(&bytes);
+                    }
+                }
+            });
+            self.texture.draw(world, pixels_per_cell);
+        }
+        let bottom = (CONTROLS_PANE_HEIGHT + WORLD_PANE_SIZE) as f32;
+        if self.grid && pixels_per_cell >= MIN_GRID_SPACING {
+            let top = bottom - world.grid.rows as f32 * pixels_per_cell;
+            let right = world.grid.columns as f32 * pixels_per_cell;
+            let colour = Color::new(0.0, 0.0, 0.0, 0.25);
+            for column in 0..=world.grid.columns {
+                let x = column as f32 * pixels_per_cell;
+                draw_line(x, top, x, bottom, 1.0, colour);
+            }
+            for row in 0..=world.grid.rows {
+                let y = bottom - row as f32 * pixels_per_cell;
+                draw_line(0.0, y, right, y, 1.0, colour);
+            }
+        } else if self.grid {
+            // Say why the grid is not drawn, rather than leaving the key to seem to do nothing
+            draw_text(
+                "Grid hidden: cells too small",
+                10.0,
+                CONTROLS_PANE_HEIGHT as f32 + 25.0,
+                20.0,
+                WHITE,
+            );
+        }
+        if self.chunks {
+            let chunk_size = CHUNK_SIZE as f32 * pixels_per_cell;
+            for chunk_row in 0..world.chunks.rows {
+                for chunk_column in 0..world.chunks.columns {
+                    if world.chunks.is_active(chunk_row, chunk_column) {
+                        // Chunks at the top and right edges may be cut short by the edges of the world
+                        let width =
+                            (world.grid.columns - chunk_column * CHUNK_SIZE).min(CHUNK_SIZE);
+                        let height = (world.grid.rows - chunk_row * CHUNK_SIZE).min(CHUNK_SIZE);
+                        draw_rectangle_lines(
+                            chunk_column as f32 * chunk_size,
+                            bottom
+                                - chunk_row as f32 * chunk_size
+                                - height as f32 * pixels_per_cell,
+                            width as f32 * pixels_per_cell,
+                            height as f32 * pixels_per_cell,
+                            2.0,
+                            GREEN,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Performs a step, recording what it did for the overlays and statistics
+fn step(world: &mut World, overlays: &mut Overlays, statistics: &mut Statistics) {
+    let start = get_time();
+    world.update();
+    let end = get_time();
+    statistics.record(world, end, end - start);
+    overlays.record_step(world);
+}
+
+// A panel over the world pane showing how many cells there are of each type over the last few hundred steps, as a chart
+// with a line for each type in its colour, along with how fast the simulation is running. S shows or hides it, and E
+// saves the statistics recorded since the simulation started (or restarted) to STATS_PATH as CSV, for at most the last
+// stats::MAX_SAMPLES steps.
+struct StatsPanel {
+    visible: bool,
+    statistics: Statistics,
+}
+
+impl StatsPanel {
+    const WIDTH: f32 = 220.0; // in pixels
+    const HEIGHT: f32 = 140.0; // in pixels
+    const CHART_HEIGHT: f32 = 90.0; // in pixels
+    const CHART_STEPS: usize = 200; // Steps shown by the chart
+    const UPDATE_TIME_STEPS: usize = 50; // Steps the update time is averaged over
+    #[cfg(not(target_arch = "wasm32"))]
+    const STATS_PATH: &'static str = "stats.csv";
+
+    fn new() -> Self {
+        StatsPanel {
+            visible: false,
+            statistics: Statistics::new(),
+        }
+    }
+
+    fn handle_keys(&mut self) {
+        if is_key_pressed(KeyCode::S) {
+            self.visible = !self.visible;
+        }
+        // There is no file system to save to in the browser
+        #[cfg(not(target_arch = "wasm32"))]
+        if is_key_pressed(KeyCode::E) {
+            match std::fs::File::create(Self::STATS_PATH)
+                .and_then(|file| self.statistics.write_csv(std::io::BufWriter::new(file)))
+            {
+                Ok(()) => info!("Statistics saved to {}", Self::STATS_PATH),
+                Err(error) => error!("Could not save statistics: {}", error),
+            }
+        }
+    }
+
+    fn draw(&self, world: &World) {
+        if !self.visible {
+            return;
+        }
+        let left = WORLD_PANE_SIZE as f32 - Self::WIDTH - 10.0;
+        let top = CONTROLS_PANE_HEIGHT as f32 + 10.0;
+        draw_rectangle(
+            left,
+            top,
+            Self::WIDTH,
+            Self::HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        let samples = &self.statistics.samples;
+        let recent = samples.len().min(Self::UPDATE_TIME_STEPS);
+        let update_time = samples
+            .iter()
+            .rev()
+            .take(recent)
+            .map(|sample| sample.update_time)
+            .sum::<f64>()
+            / recent.max(1) as f64;
+        draw_text(
+            &format!(
+                "{:.0} steps/s, {:.2} ms/step",
+                self.statistics.steps_per_second(),
+                update_time * 1000.0
+            ),
+            left + 8.0,
+            top + 20.0,
+            18.0,
+            WHITE,
+        );
+        // Draw the number of cells of each type (other than empty cells) as a fraction of the world
+        let chart: Vec<_> = samples
+            .iter()
+            .skip(samples.len().saturating_sub(Self::CHART_STEPS))
+            .collect();
+        let cells = (world.grid.rows * world.grid.columns) as f32;
+        let bottom = top + Self::HEIGHT - 10.0;
+        let x = |index: usize| {
+            left + 10.0 + index as f32 * (Self::WIDTH - 20.0) / Self::CHART_STEPS as f32
+        };
+        let y = |count: u32| bottom - count as f32 / cells * Self::CHART_HEIGHT;
+        for cell_type in CellType::ALL.into_iter().skip(1) {
+            let code = cell_type as usize;
+            if chart.iter().all(|sample| sample.counts[code] == 0) {
+                continue;
+            }
+            let colour = colours::material_colour(world, cell_type);
+            let colour = Color::new(colour.red, colour.green, colour.blue, 1.0);
+            for (index, pair) in chart.windows(2).enumerate() {
+                draw_line(
+                    x(index),
+                    y(pair[0].counts[code]),
+                    x(index + 1),
+                    y(pair[1].counts[code]),
+                    1.0,
+                    colour,
+                );
+            }
+        }
+    }
+}
+
+fn draw(
+    world: &World,
+    world_texture: &mut CellTexture,
+    overlays: &mut Overlays,
+    stats_panel: &StatsPanel,
+    pixels_per_cell: f32,
+    depth_shading: bool,
+) {
     // Draw controls pane background
     draw_rectangle(
         0.0,
@@ -135,53 +623,22 @@ fn draw(world: &World, pixels_per_cell: f32) {
         CONTROLS_PANE_HEIGHT as f32,
         WHITE,
     );
-    // Draw world background
+    // Draw world background, which shows around worlds that are not square
+    let background = colours::BACKGROUND;
     draw_rectangle(
         0.0,
         CONTROLS_PANE_HEIGHT as f32,
         WORLD_PANE_SIZE as f32,
         WORLD_PANE_SIZE as f32,
-        GRAY,
+        Color::new(background.red, background.green, background.blue, 1.0),
     );
     // Draw world
-    let mut cell_type: CellType;
-    for row in 0..world.grid.rows {
-        for column in 0..world.grid.columns {
-            cell_type = world.grid.cells[row][column].cell_type;
-            if world.grid.cells[row][column].cell_type != CellType::Empty {
-                draw_rectangle(
-                    column as f32 * pixels_per_cell,
-                    CONTROLS_PANE_HEIGHT as f32 + WORLD_PANE_SIZE as f32
-                        - ((row as f32) * pixels_per_cell + pixels_per_cell),
-                    pixels_per_cell,
-                    pixels_per_cell,
-                    if cell_type == CellType::Wall {
-                        BLACK
-                    } else if cell_type == CellType::Sand {
-                        BEIGE
-                    } else if cell_type == CellType::Water {
-                        if row < (world.grid.rows - 1) {
-                            if world.grid.cells[row + 1][column].cell_type == CellType::Empty {
-                                SKYBLUE
-                            } else {
-                                BLUE
-                            }
-                        } else {
-                            SKYBLUE
-                        }
-                    } else if cell_type == CellType::Steam {
-                        LIGHTGRAY
-                    } else if cell_type == CellType::Oil {
-                        DARKBROWN
-                    } else if cell_type == CellType::Wood {
-                        BROWN
-                    } else {
-                        panic!("Invalid cell type");
-                    },
-                )
-            };
-        }
-    }
+    world_texture.update(world, |pixels| {
+        colours::write_rgba8(world, depth_shading, pixels)
+    });
+    world_texture.draw(world, pixels_per_cell);
+    overlays.draw(world, pixels_per_cell);
+    stats_panel.draw(world);
     // Draw FPS and step count
     draw_text(&format!("FPS: {}", get_fps()), 310.0, 25.0, 20.0, DARKGRAY);
     draw_text(
@@ -201,7 +658,25 @@ fn toggle(current_type: CellType) -> CellType {
         CellType::Sand => CellType::Water,
         CellType::Water => CellType::Oil,
         CellType::Oil => CellType::Steam,
-        CellType::Steam => CellType::Empty,
+        CellType::Steam => CellType::Lava,
+        CellType::Lava => CellType::Stone,
+        CellType::Stone => CellType::Obsidian,
+        CellType::Obsidian => CellType::Fire,
+        CellType::Fire => CellType::Acid,
+        CellType::Acid => CellType::Glass,
+        CellType::Glass => CellType::Ice,
+        CellType::Ice => CellType::Snow,
+        CellType::Snow => CellType::Seed,
+        CellType::Seed => CellType::Plant,
+        CellType::Plant => CellType::Gunpowder,
+        CellType::Gunpowder => CellType::Tnt,
+        CellType::Tnt => CellType::Smoke,
+        CellType::Smoke => CellType::ToxicGas,
+        CellType::ToxicGas => CellType::Custom1,
+        CellType::Custom1 => CellType::Custom2,
+        CellType::Custom2 => CellType::Custom3,
+        CellType::Custom3 => CellType::Custom4,
+        CellType::Custom4 => CellType::Empty,
     }
 }
 
@@ -218,25 +693,6 @@ where
     }
 }
 
-fn populate_world() -> World {
-    let mut world = World::new(examples::EXAMPLE_1.0);
-    for row in 0..examples::EXAMPLE_1.0 {
-        for column in 0..examples::EXAMPLE_1.0 {
-            world.grid.cells[row][column].cell_type = match examples::EXAMPLE_1.1[row][column] {
-                0 => CellType::Empty,
-                1 => CellType::Wall,
-                2 => CellType::Wood,
-                3 => CellType::Sand,
-                4 => CellType::Water,
-                5 => CellType::Oil,
-                6 => CellType::Steam,
-                _ => CellType::Empty,
-            };
-        }
-    }
-    world
-}
-
 // Prints the current world to the console - not currently used
 // fn print_world(world: &World) {
 //     let mut cells: Vec<Vec<u8>> = vec![vec![0; world.world_size]; world.world_size];
@@ -246,5 +702,5 @@ fn populate_world() -> World {
 //         }
 //     }
 //     let world_state: (usize, Vec<Vec<u8>>) = (world.world_size, cells);
-//     println!("Cells: {:?}", world_state);
+//     info!("Cells: {:?}", world_state);
 // }
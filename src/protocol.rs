@@ -0,0 +1,292 @@
+// A line-oriented text protocol for driving the simulation from scripts and other programs (see the rust-fall-headless
+// binary), built on the engine's public World API.
+//
+// The engine first writes a greeting, 'rust-fall protocol <version>'. It then reads one command per line and answers
+// each with a single line, 'ok' (followed by the result, if the command has one) or 'error <message>'. The only
+// exception is dump, whose 'ok <n>' line is followed by the n rows of the world. Blank lines and lines starting with
+// '#' are ignored without an answer, so that scripts can be commented. Reaching the end of the input has the same
+// effect as quit.
+//
+// Rows are counted from the bottom of the world and columns from the left, both starting at 0. Cell types are given by
+// their identifiers, i.e. their names in lower case with spaces replaced by underscores (e.g. sand, toxic_gas).
+//
+// Commands:
+//     version                              The version of the protocol, which is increased whenever it changes
+//                                          incompatibly
+//     new <rows> <columns>                 Replace the world with an empty one of the given size
+//     load <path>                          Replace the world with one loaded from a file in the text format (see the
+//                                          engine's text module)
+//     save <path>                          Save the world to a file in the text format
+//     size                                 The number of rows and columns in the world
+//     seed <seed>                          Reseed the world's random number generator
+//     step [<count>]                       Perform the given number of steps (1 by default), answering with the total
+//                                          number of steps performed
+//     get <row> <column>                   The type of a cell
+//     set <row> <column> <type>            Set the type of a cell
+//     fill_rect <row> <column> <row> <column> <type>
+//                                          Set the type of every cell in the rectangle with the given opposite corners
+//     clear                                Set every cell to empty
+//     count <type>                         The number of cells of a type
+//     gravity <down|up|left|right|off>     Set the direction of gravity
+//     momentum <on|off>                    Switch momentum on or off
+//     dump <text|rle>                      The world, one row per line starting with the top row, in the text format
+//                                          or run-length encoded (each run of cells as their number, if more than
+//                                          one, followed by the character for their type, e.g. '3.2s.' for '...ss.')
+//     stats <start|stop>                   Start recording statistics for each step performed (discarding any
+//                                          already recorded, and keeping the last stats::MAX_SAMPLES), or stop
+//                                          recording them
+//     stats save <path>                    Save the recorded statistics to a file as CSV (see the stats module), with
+//                                          times measured from when recording started
+//     quit                                 Stop reading commands
+
+use crate::engine::{CellType, Gravity, World};
+use crate::stats::Statistics;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+use std::time::Instant;
+
+pub const VERSION: u32 = 1;
+
+const DEFAULT_SIZE: usize = 64; // Width and height of the world before any new or load command
+
+// Statistics recorded for the steps performed (see the stats command)
+struct Recording {
+    statistics: Statistics,
+    start: Instant, // When recording started, from which the times of the steps are measured
+    active: bool,
+}
+
+// Reads commands from the input and writes the answers to the output until the input ends or quit is read
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut world = World::new(DEFAULT_SIZE);
+    let mut recording = Recording {
+        statistics: Statistics::new(),
+        start: Instant::now(),
+        active: false,
+    };
+    writeln!(output, "rust-fall protocol {}", VERSION)?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() || words[0].starts_with('#') {
+            continue;
+        }
+        if words == ["quit"] {
+            writeln!(output, "ok")?;
+            break;
+        }
+        match execute(&mut world, &mut recording, &words) {
+            Ok(result) if result.is_empty() => writeln!(output, "ok")?,
+            Ok(result) => writeln!(output, "ok {}", result)?,
+            Err(message) => writeln!(output, "error {}", message)?,
+        }
+        output.flush()?;
+    }
+    output.flush()
+}
+
+// Performs a command, returning its result (which is empty for commands without one)
+fn execute(world: &mut World, recording: &mut Recording, words: &[&str]) -> Result<String, String> {
+    let arguments = &words[1..];
+    match words[0] {
+        "version" => {
+            expect_arguments(arguments, 0)?;
+            Ok(VERSION.to_string())
+        }
+        "new" => {
+            expect_arguments(arguments, 2)?;
+            let rows: usize = parse_number(arguments[0])?;
+            let columns: usize = parse_number(arguments[1])?;
+            if rows == 0 || columns == 0 {
+                return Err("The world must have at least one row and column".to_owned());
+            }
+            *world = World::with_size(rows, columns);
+            Ok(String::new())
+        }
+        "load" => {
+            expect_arguments(arguments, 1)?;
+            let text = fs::read_to_string(arguments[0]).map_err(|error| error.to_string())?;
+            *world = World::from_text(&text)?;
+            Ok(String::new())
+        }
+        "save" => {
+            expect_arguments(arguments, 1)?;
+            fs::write(arguments[0], world.to_text()).map_err(|error| error.to_string())?;
+            Ok(String::new())
+        }
+        "size" => {
+            expect_arguments(arguments, 0)?;
+            Ok(format!("{} {}", world.grid.rows, world.grid.columns))
+        }
+        "seed" => {
+            expect_arguments(arguments, 1)?;
+            world.seed(parse_number(arguments[0])?);
+            Ok(String::new())
+        }
+        "step" => {
+            if arguments.len() > 1 {
+                return Err("Expected at most 1 argument".to_owned());
+            }
+            let count: usize = match arguments.first() {
+                Some(count) => parse_number(count)?,
+                None => 1,
+            };
+            for _ in 0..count {
+                let start = Instant::now();
+                world.update();
+                if recording.active {
+                    let time = recording.start.elapsed().as_secs_f64();
+                    let update_time = start.elapsed().as_secs_f64();
+                    recording.statistics.record(world, time, update_time);
+                }
+            }
+            Ok(world.steps.to_string())
+        }
+        "get" => {
+            expect_arguments(arguments, 2)?;
+            let (row, column) = parse_position(world, arguments[0], arguments[1])?;
+            Ok(world.grid.cells[row][column].cell_type.identifier())
+        }
+        "set" => {
+            expect_arguments(arguments, 3)?;
+            let (row, column) = parse_position(world, arguments[0], arguments[1])?;
+            world.set_cell(row, column, parse_cell_type(arguments[2])?);
+            Ok(String::new())
+        }
+        "fill_rect" => {
+            expect_arguments(arguments, 5)?;
+            let (row_1, column_1) = parse_position(world, arguments[0], arguments[1])?;
+            let (row_2, column_2) = parse_position(world, arguments[2], arguments[3])?;
+            let cell_type = parse_cell_type(arguments[4])?;
+            for row in row_1.min(row_2)..=row_1.max(row_2) {
+                for column in column_1.min(column_2)..=column_1.max(column_2) {
+                    world.set_cell(row, column, cell_type);
+                }
+            }
+            Ok(String::new())
+        }
+        "clear" => {
+            expect_arguments(arguments, 0)?;
+            world.clear();
+            Ok(String::new())
+        }
+        "count" => {
+            expect_arguments(arguments, 1)?;
+            let cell_type = parse_cell_type(arguments[0])?;
+            let count = world
+                .grid
+                .cells
+                .iter()
+                .flatten()
+                .filter(|cell| cell.cell_type == cell_type)
+                .count();
+            Ok(count.to_string())
+        }
+        "gravity" => {
+            expect_arguments(arguments, 1)?;
+            world.gravity = match arguments[0] {
+                "down" => Gravity::Down,
+                "up" => Gravity::Up,
+                "left" => Gravity::Left,
+                "right" => Gravity::Right,
+                "off" => Gravity::Off,
+                other => return Err(format!("Unknown direction '{}'", other)),
+            };
+            Ok(String::new())
+        }
+        "momentum" => {
+            expect_arguments(arguments, 1)?;
+            world.momentum = match arguments[0] {
+                "on" => true,
+                "off" => false,
+                other => return Err(format!("Expected on or off, not '{}'", other)),
+            };
+            Ok(String::new())
+        }
+        "dump" => {
+            expect_arguments(arguments, 1)?;
+            let text = world.to_text();
+            let rows: Vec<String> = match arguments[0] {
+                "text" => text.lines().map(str::to_owned).collect(),
+                "rle" => text.lines().map(run_length_encode).collect(),
+                other => return Err(format!("Unknown format '{}'", other)),
+            };
+            Ok(format!("{}\n{}", rows.len(), rows.join("\n")))
+        }
+        "stats" => match arguments {
+            ["start"] => {
+                recording.statistics.clear();
+                recording.start = Instant::now();
+                recording.active = true;
+                Ok(String::new())
+            }
+            ["stop"] => {
+                recording.active = false;
+                Ok(String::new())
+            }
+            ["save", path] => {
+                let mut csv = Vec::new();
+                recording
+                    .statistics
+                    .write_csv(&mut csv)
+                    .and_then(|_| fs::write(path, csv))
+                    .map_err(|error| error.to_string())?;
+                Ok(String::new())
+            }
+            _ => Err("Expected start, stop or save <path>".to_owned()),
+        },
+        other => Err(format!("Unknown command '{}'", other)),
+    }
+}
+
+fn expect_arguments(arguments: &[&str], expected: usize) -> Result<(), String> {
+    if arguments.len() == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected {} argument{}, but got {}",
+            expected,
+            if expected == 1 { "" } else { "s" },
+            arguments.len()
+        ))
+    }
+}
+
+fn parse_number<T: FromStr>(word: &str) -> Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("Expected a number, not '{}'", word))
+}
+
+fn parse_position(world: &World, row: &str, column: &str) -> Result<(usize, usize), String> {
+    let (row, column) = (parse_number(row)?, parse_number(column)?);
+    if row < world.grid.rows && column < world.grid.columns {
+        Ok((row, column))
+    } else {
+        Err(format!(
+            "Cell {} {} is outside the world ({} rows, {} columns)",
+            row, column, world.grid.rows, world.grid.columns
+        ))
+    }
+}
+
+fn parse_cell_type(word: &str) -> Result<CellType, String> {
+    CellType::from_identifier(word).ok_or(format!("Unknown cell type '{}'", word))
+}
+
+fn run_length_encode(row: &str) -> String {
+    let mut encoded = String::new();
+    let mut characters = row.chars().peekable();
+    while let Some(character) = characters.next() {
+        let mut run = 1;
+        while characters.next_if_eq(&character).is_some() {
+            run += 1;
+        }
+        if run > 1 {
+            encoded.push_str(&run.to_string());
+        }
+        encoded.push(character);
+    }
+    encoded
+}
@@ -0,0 +1,136 @@
+// Statistics recorded for each step of a simulation (the number of cells of each type and how long the step took), used
+// to check that materials are conserved and to spot steps that are slow, and exported as CSV for analysis elsewhere.
+//
+// The recorder measures the time, so that the statistics work the same way on every target (std::time::Instant is not
+// available on wasm32). Recording costs little more than the step itself: the cells are counted chunk by chunk, and
+// after each step only the chunks that were updated or woken in it are recounted, as no others can have changed (see
+// the engine's chunks module), so a world that has settled is almost free to record.
+
+use crate::engine::{CellType, World, CHUNK_SIZE};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+pub const MAX_SAMPLES: usize = 100_000; // Samples kept before the oldest are discarded (over half an hour at 50/s)
+
+pub struct Sample {
+    pub step: u32,
+    pub time: f64, // When the step finished (in seconds, from a starting point chosen by the recorder)
+    pub update_time: f64, // How long the step took (in seconds)
+    pub counts: Counts, // Number of cells of each type after the step
+}
+
+pub type Counts = [u32; CellType::ALL.len()]; // Number of cells of each type, in order of their codes
+
+#[derive(Default)]
+pub struct Statistics {
+    pub samples: VecDeque<Sample>,
+    size: (usize, usize), // Rows and columns of the world the chunks were counted in
+    chunk_counts: Vec<Counts>, // Number of cells of each type in each chunk, row by row starting with the bottom row
+    counts: Counts,            // Totals of the chunk counts
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics::default()
+    }
+
+    // Records the world after a step, given when the step finished and how long it took
+    pub fn record(&mut self, world: &World, time: f64, update_time: f64) {
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.update_counts(world);
+        self.samples.push_back(Sample {
+            step: world.steps,
+            time,
+            update_time,
+            counts: self.counts,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    // Recounts the chunks that may have changed since the last step recorded, or every chunk if the world is not the
+    // one last recorded
+    fn update_counts(&mut self, world: &World) {
+        let chunks = &world.chunks;
+        let size = (world.grid.rows, world.grid.columns);
+        let recount_all =
+            self.size != size || self.chunk_counts.len() != chunks.rows * chunks.columns;
+        if recount_all {
+            self.size = size;
+            self.chunk_counts = vec![[0; CellType::ALL.len()]; chunks.rows * chunks.columns];
+            self.counts = [0; CellType::ALL.len()];
+        }
+        for chunk_row in 0..chunks.rows {
+            for chunk_column in 0..chunks.columns {
+                if !recount_all
+                    && !chunks.is_active(chunk_row, chunk_column)
+                    && !chunks.is_awake(chunk_row, chunk_column)
+                {
+                    continue;
+                }
+                let mut chunk_counts = [0; CellType::ALL.len()];
+                for row in
+                    chunk_row * CHUNK_SIZE..((chunk_row + 1) * CHUNK_SIZE).min(world.grid.rows)
+                {
+                    let cells = &world.grid.cells[row];
+                    for cell in &cells[chunk_column * CHUNK_SIZE
+                        ..((chunk_column + 1) * CHUNK_SIZE).min(world.grid.columns)]
+                    {
+                        chunk_counts[cell.cell_type as usize] += 1;
+                    }
+                }
+                let old_counts = &mut self.chunk_counts[chunk_row * chunks.columns + chunk_column];
+                for code in 0..CellType::ALL.len() {
+                    self.counts[code] = self.counts[code] - old_counts[code] + chunk_counts[code];
+                }
+                *old_counts = chunk_counts;
+            }
+        }
+    }
+
+    // Returns the number of steps per second over the last second of samples (0 if there are fewer than two)
+    pub fn steps_per_second(&self) -> f64 {
+        let Some(last) = self.samples.back() else {
+            return 0.0;
+        };
+        let recent = self
+            .samples
+            .iter()
+            .rev()
+            .take_while(|sample| sample.time >= last.time - 1.0);
+        match recent.last() {
+            Some(first) if first.time < last.time => {
+                last.step.saturating_sub(first.step) as f64 / (last.time - first.time)
+            }
+            _ => 0.0,
+        }
+    }
+
+    // Writes the samples as CSV, with a header row followed by one row per step: the step, time and update time (in
+    // milliseconds), then the number of cells of each type, headed by its identifier (see CellType::identifier)
+    pub fn write_csv(&self, mut output: impl Write) -> io::Result<()> {
+        write!(output, "step,time,update_ms")?;
+        for cell_type in CellType::ALL {
+            write!(output, ",{}", cell_type.identifier())?;
+        }
+        writeln!(output)?;
+        for sample in &self.samples {
+            write!(
+                output,
+                "{},{:.6},{:.3}",
+                sample.step,
+                sample.time,
+                sample.update_time * 1000.0
+            )?;
+            for count in sample.counts {
+                write!(output, ",{}", count)?;
+            }
+            writeln!(output)?;
+        }
+        Ok(())
+    }
+}
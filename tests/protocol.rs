@@ -0,0 +1,148 @@
+// Tests of the text protocol (see the protocol module), run as scripts of commands with each answer checked in turn
+
+use rust_fall::protocol;
+
+fn run(script: &str) -> Vec<String> {
+    let mut output = Vec::new();
+    protocol::run(script.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn commands_are_answered() {
+    let output = run("
+        # Sand poured onto a wall settles onto it
+        version
+        new 8 6
+        size
+        seed 1
+        fill_rect 1 0 1 5 wall
+        set 7 2 sand
+        get 7 2
+        step 20
+        get 2 2
+        count sand
+        count wall
+        dump rle
+        dump text
+        quit
+        step
+    ");
+    assert_eq!(
+        output,
+        [
+            "rust-fall protocol 1",
+            "ok 1",
+            "ok",
+            "ok 8 6",
+            "ok",
+            "ok",
+            "ok",
+            "ok sand",
+            "ok 20",
+            "ok sand",
+            "ok 1",
+            "ok 6",
+            "ok 8",
+            "6.",
+            "6.",
+            "6.",
+            "6.",
+            "6.",
+            "2.s3.",
+            "6#",
+            "6.",
+            "ok 8",
+            "......",
+            "......",
+            "......",
+            "......",
+            "......",
+            "..s...",
+            "######",
+            "......",
+            "ok",
+        ]
+    );
+}
+
+#[test]
+fn mistakes_are_reported() {
+    let output = run("
+        new 4 4
+        set 4 0 sand
+        set 0 0 quicksand
+        step many
+        count
+        fly
+        gravity sideways
+    ");
+    assert_eq!(
+        output,
+        [
+            "rust-fall protocol 1",
+            "ok",
+            "error Cell 4 0 is outside the world (4 rows, 4 columns)",
+            "error Unknown cell type 'quicksand'",
+            "error Expected a number, not 'many'",
+            "error Expected 1 argument, but got 0",
+            "error Unknown command 'fly'",
+            "error Unknown direction 'sideways'",
+        ]
+    );
+}
+
+#[test]
+fn statistics_are_saved() {
+    let path = std::env::temp_dir().join(format!("rust-fall-stats-{}.csv", std::process::id()));
+    let output = run(&format!(
+        "
+        new 4 4
+        set 3 1 sand
+        set 0 0 wall
+        step
+        stats start
+        step 2
+        stats stop
+        step
+        stats save {}
+        stats pause
+        ",
+        path.display()
+    ));
+    assert_eq!(
+        output,
+        [
+            "rust-fall protocol 1",
+            "ok",
+            "ok",
+            "ok",
+            "ok 1",
+            "ok",
+            "ok 3",
+            "ok",
+            "ok 4",
+            "ok",
+            "error Expected start, stop or save <path>",
+        ]
+    );
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    // Only the steps performed while recording are saved, with the number of cells of each type after them
+    assert_eq!(rows.len(), 3);
+    assert_eq!(
+        rows[0][..6],
+        ["step", "time", "update_ms", "empty", "wall", "wood"]
+    );
+    assert_eq!(rows[0].len(), 3 + 25);
+    for (row, step) in rows[1..].iter().zip(["2", "3"]) {
+        assert_eq!(row[0], step);
+        assert_eq!(row[3..6], ["14", "1", "0"]);
+        assert_eq!(row[6], "1"); // Sand
+    }
+}
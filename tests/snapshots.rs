@@ -0,0 +1,294 @@
+// Golden snapshot tests, which document what each material is supposed to do.
+//
+// Each scenario is a hand-authored world that is run (with a fixed seed) for a number of steps, and the result is
+// compared against the expected world stored in tests/snapshots/<scenario name>.txt (see the engine's text module for
+// the format). When a rule is changed on purpose, re-bless the snapshots with:
+//     BLESS=1 cargo test --test snapshots
+// and check the differences in the snapshot files before committing them.
+
+use rust_fall::engine::World;
+use std::fs;
+use std::path::PathBuf;
+
+const SEED: u64 = 0;
+
+struct Scenario {
+    name: &'static str,
+    steps: usize,
+    world: &'static [&'static str], // Initial world, in the text format
+}
+
+const SCENARIOS: [Scenario; 9] = [
+    // Sand poured onto a flat floor settles into a pile
+    Scenario {
+        name: "sand_pile",
+        steps: 100,
+        world: &[
+            "......ssss......",
+            "......ssss......",
+            "......ssss......",
+            "......ssss......",
+            "......ssss......",
+            "......ssss......",
+            "......ssss......",
+            "......ssss......",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "################",
+        ],
+    },
+    // Snow piles more steeply than sand
+    Scenario {
+        name: "snow_pile",
+        steps: 100,
+        world: &[
+            "......****......",
+            "......****......",
+            "......****......",
+            "......****......",
+            "......****......",
+            "......****......",
+            "......****......",
+            "......****......",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "################",
+        ],
+    },
+    // Water poured into one arm of a U-tube runs along the bottom of the tube, but as there is no pressure it stays
+    // high in the arm it was poured into rather than rising up the other arm to the same level
+    Scenario {
+        name: "u_tube",
+        steps: 800,
+        world: &[
+            "................",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www######...#.",
+            ".#www.........#.",
+            ".#www.........#.",
+            ".##############.",
+            "................",
+            "................",
+        ],
+    },
+    // Water poured into a trough on one side of a wall hanging down into it runs under the wall and levels out at the
+    // same height on both sides of it
+    Scenario {
+        name: "water_fills_trough",
+        steps: 800,
+        world: &[
+            "................",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#www#....#...#.",
+            ".#...######...#.",
+            ".#............#.",
+            ".#............#.",
+            ".#............#.",
+            ".##############.",
+            "................",
+            "................",
+        ],
+    },
+    // Oil is lighter than water, so it floats to the top
+    Scenario {
+        name: "oil_on_water",
+        steps: 200,
+        world: &[
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "#..............#",
+            "#wwwwwwwwwwwwww#",
+            "#wwwwwwwwwwwwww#",
+            "#wwwwwwwwwwwwww#",
+            "#oooooooooooooo#",
+            "#oooooooooooooo#",
+            "#oooooooooooooo#",
+            "#wwwwwwwwwwwwww#",
+            "#wwwwwwwwwwwwww#",
+            "################",
+        ],
+    },
+    // Steam rises to the ceiling
+    Scenario {
+        name: "steam_rises",
+        steps: 100,
+        world: &[
+            "################",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#..............#",
+            "#....~~~~~~....#",
+            "#....~~~~~~....#",
+            "#....~~~~~~....#",
+            "################",
+        ],
+    },
+    // Lava flowing into water cools into stone (or obsidian where it is surrounded), boiling the water into steam
+    Scenario {
+        name: "lava_meets_water",
+        steps: 200,
+        world: &[
+            "................",
+            "..LLLL..........",
+            "..LLLL..........",
+            "..LLLL..........",
+            "..LLLL..........",
+            "................",
+            "................",
+            "................",
+            "#..............#",
+            "#..............#",
+            "#wwwwwwwwwwwwww#",
+            "#wwwwwwwwwwwwww#",
+            "#wwwwwwwwwwwwww#",
+            "#wwwwwwwwwwwwww#",
+            "#wwwwwwwwwwwwww#",
+            "################",
+        ],
+    },
+    // Fire spreads through wood, burning out into smoke
+    Scenario {
+        name: "fire_burns_wood",
+        steps: 150,
+        world: &[
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "................",
+            "....WWWWWWWW....",
+            "....WWWWWWWW....",
+            "....WWWWWWWW....",
+            "....WWWWWWWW....",
+            "....WWWWWWWWf...",
+            "................",
+            "................",
+            "................",
+            "################",
+        ],
+    },
+    // Acid dissolves sand, giving off toxic gas, but cannot eat through glass
+    Scenario {
+        name: "acid_dissolves_sand",
+        steps: 200,
+        world: &[
+            "................",
+            "...aaaaaaaaaa...",
+            "...aaaaaaaaaa...",
+            "................",
+            "..G..........G..",
+            "..G..........G..",
+            "..G..........G..",
+            "..GssssssssssG..",
+            "..GssssssssssG..",
+            "..GssssssssssG..",
+            "..GssssssssssG..",
+            "..GGGGGGGGGGGG..",
+            "................",
+            "................",
+            "................",
+            "################",
+        ],
+    },
+];
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name))
+}
+
+#[test]
+fn snapshots() {
+    let bless = std::env::var_os("BLESS").is_some();
+    let mut failures = Vec::new();
+    for scenario in &SCENARIOS {
+        let mut world = World::from_text(&scenario.world.join("\n"))
+            .unwrap_or_else(|error| panic!("Scenario {} is invalid: {}", scenario.name, error));
+        world.seed(SEED);
+        // The snapshots show the materials as they behave in the window and the terminal, which switch momentum on
+        world.momentum = true;
+        for _ in 0..scenario.steps {
+            world.update();
+        }
+        let actual = world.to_text();
+        let path = snapshot_path(scenario.name);
+        if bless {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        if actual != expected {
+            failures.push(format!(
+                "{} after {} steps\n{:<width$}  actual\n{}",
+                scenario.name,
+                scenario.steps,
+                "expected",
+                side_by_side(&expected, &actual),
+                width = scenario.world[0].len()
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "Snapshots do not match (re-bless with 'BLESS=1 cargo test --test snapshots' if the change is intended):\n\n{}",
+        failures.join("\n")
+    );
+}
+
+// Lays out the lines of two worlds next to each other
+fn side_by_side(left: &str, right: &str) -> String {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let width = left.iter().map(|line| line.len()).max().unwrap_or(0);
+    let mut text = String::new();
+    for line in 0..left.len().max(right.len()) {
+        text.push_str(&format!(
+            "{:<width$}  {}\n",
+            left.get(line).unwrap_or(&""),
+            right.get(line).unwrap_or(&""),
+            width = width
+        ));
+    }
+    text
+}
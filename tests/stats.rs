@@ -0,0 +1,40 @@
+// Tests of the per-step statistics (see the stats module)
+
+use rust_fall::engine::{CellType, World};
+use rust_fall::examples;
+use rust_fall::stats::{Counts, Statistics};
+
+fn count_cells(world: &World) -> Counts {
+    let mut counts = [0; CellType::ALL.len()];
+    for cell in world.grid.cells.iter().flatten() {
+        counts[cell.cell_type as usize] += 1;
+    }
+    counts
+}
+
+#[test]
+fn counts_follow_the_world() {
+    // The chunks are only recounted when they may have changed, so check the counts against a full count as the example
+    // settles and goes to sleep, is painted on while asleep and is replaced by a world of a different size
+    let mut world = examples::populate_world(&examples::EXAMPLE_1, 1);
+    world.seed(1);
+    let mut statistics = Statistics::new();
+    for step in 0..600 {
+        if step == 400 {
+            world.set_cell(100, 100, CellType::Sand);
+            world.set_cell(5, 120, CellType::Wall);
+        }
+        if step == 500 {
+            world = World::with_size(40, 70);
+            world.set_cell(39, 69, CellType::Water);
+        }
+        world.update();
+        statistics.record(&world, step as f64, 0.0);
+        assert_eq!(
+            statistics.samples.back().unwrap().counts,
+            count_cells(&world),
+            "Step {}",
+            step
+        );
+    }
+}